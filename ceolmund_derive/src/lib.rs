@@ -0,0 +1,119 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Fields, Ident, LitInt, Error};
+
+/// Derive Serializable for a unit enum that is registered in a namespace or for a struct with named fields.
+/// Unit enums need `#[serializable(namespace = <namespace field>)]` and are saved as their namespaced ID.
+/// Struct fields are saved in order, a field added in a later serialization version needs `#[serializable(since = <version>, default = <expr>)]`.
+#[proc_macro_derive(Serializable, attributes(serializable))]
+pub fn derive_serializable(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let out = match &input.data {
+		Data::Enum(_) => derive_for_enum(&input),
+		Data::Struct(data) => derive_for_struct(&input, &data.fields),
+		Data::Union(_) => Err(Error::new_spanned(&input.ident, "Serializable can not be derived for unions.")),
+	};
+	match out {
+		Ok(out) => out.into(),
+		Err(error) => error.to_compile_error().into(),
+	}
+}
+
+/// Save a unit enum as the ID of the variant and load it with the table of a namespace.
+fn derive_for_enum(input: &DeriveInput) -> Result<TokenStream2, Error> {
+	let name = &input.ident;
+	let Data::Enum(data) = &input.data else { unreachable!() };
+	for variant in data.variants.iter() {
+		if !matches!(variant.fields, Fields::Unit) {
+			return Err(Error::new_spanned(variant, "Serializable can only be derived for enums without variant fields."));
+		}
+	}
+	let mut namespace_field: Option<Ident> = None;
+	for attribute in input.attrs.iter().filter(|attribute| attribute.path().is_ident("serializable")) {
+		attribute.parse_nested_meta(|meta| {
+			match meta.path.is_ident("namespace") {
+				true => {
+					namespace_field = Some(meta.value()?.parse()?);
+					Ok(())
+				}
+				false => Err(meta.error("Expected namespace.")),
+			}
+		})?;
+	}
+	let namespace_field = namespace_field.ok_or_else(|| Error::new_spanned(name, "Enums need #[serializable(namespace = <namespace field>)]."))?;
+	let variants = data.variants.iter().enumerate().map(|(index, variant)| {
+		let variant_name = &variant.ident;
+		quote! { Self::#variant_name => #index }
+	});
+	Ok(quote! {
+		impl crate::io::serializable::Serializable for #name {
+			fn serialize(&self, file: &mut crate::io::file_writer::FileWriter) {
+				file.push_id(match self {
+					#(#variants,)*
+				});
+			}
+
+			fn deserialize(file: &mut crate::io::file_reader::FileReader, namespace: &crate::io::namespace::Namespace, version: u32) -> Result<Self, crate::error::Error> {
+				namespace.#namespace_field.read(file, version)
+			}
+		}
+	})
+}
+
+/// Save each field of a struct in order.
+fn derive_for_struct(input: &DeriveInput, fields: &Fields) -> Result<TokenStream2, Error> {
+	let name = &input.ident;
+	let fields = match fields {
+		Fields::Named(fields) => &fields.named,
+		_ => return Err(Error::new_spanned(name, "Serializable can only be derived for structs with named fields.")),
+	};
+	let mut serialize_fields = Vec::new();
+	let mut deserialize_fields = Vec::new();
+	for field in fields.iter() {
+		let field_name = field.ident.as_ref().expect("Named fields should have a name.");
+		let field_type = &field.ty;
+		let mut since: Option<LitInt> = None;
+		let mut default: Option<Expr> = None;
+		for attribute in field.attrs.iter().filter(|attribute| attribute.path().is_ident("serializable")) {
+			attribute.parse_nested_meta(|meta| {
+				if meta.path.is_ident("since") {
+					since = Some(meta.value()?.parse()?);
+					Ok(())
+				}
+				else if meta.path.is_ident("default") {
+					default = Some(meta.value()?.parse()?);
+					Ok(())
+				}
+				else {
+					Err(meta.error("Expected since or default."))
+				}
+			})?;
+		}
+		serialize_fields.push(quote! {
+			crate::io::serializable::Serializable::serialize(&self.#field_name, file);
+		});
+		deserialize_fields.push(match (since, default) {
+			(Some(since), Some(default)) => quote! {
+				#field_name: <#field_type as crate::io::serializable::Serializable>::deserialize_since(file, namespace, version, #since, || #default)?
+			},
+			(None, None) => quote! {
+				#field_name: <#field_type as crate::io::serializable::Serializable>::deserialize(file, namespace, version)?
+			},
+			_ => return Err(Error::new_spanned(field, "Fields added in a later version need both since and default.")),
+		});
+	}
+	Ok(quote! {
+		impl crate::io::serializable::Serializable for #name {
+			fn serialize(&self, file: &mut crate::io::file_writer::FileWriter) {
+				#(#serialize_fields)*
+			}
+
+			fn deserialize(file: &mut crate::io::file_reader::FileReader, namespace: &crate::io::namespace::Namespace, version: u32) -> Result<Self, crate::error::Error> {
+				Ok(Self {
+					#(#deserialize_fields,)*
+				})
+			}
+		}
+	})
+}
\ No newline at end of file
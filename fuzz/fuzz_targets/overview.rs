@@ -0,0 +1,12 @@
+#![no_main]
+
+use std::sync::OnceLock;
+
+use ceolmund::io::{load_fuzzing::{LoadTarget, create_load_storage, load_untrusted}, storage::MemoryStorage};
+use libfuzzer_sys::fuzz_target;
+
+static STORAGE: OnceLock<MemoryStorage> = OnceLock::new();
+
+fuzz_target!(|data: &[u8]| {
+	load_untrusted(STORAGE.get_or_init(create_load_storage), LoadTarget::Overview, data);
+});
\ No newline at end of file
@@ -0,0 +1,160 @@
+use std::{fs::{read_to_string, write}, path::PathBuf};
+
+use crate::{io::{io::IO, save_dump}, world::{world::World, world_upgrade::upgrade_world}, gui::load_world_data::WorldList};
+
+/// How to use the game from the command line.
+const USAGE: &str = "Usage:
+	ceolmund                                Start the game.
+	ceolmund throwaway                      Start the game with worlds kept in memory, nothing is saved.
+	ceolmund export <world> <archive file>  Export a world to a single file.
+	ceolmund import <archive file>          Import an exported world into the worlds folder.
+	ceolmund upgrade <world>                Save every file in a world with the current save format.
+	ceolmund dump <save file> [<dump file>] Write a .wld, .ent, .cnk or .nsp file as readable text.
+	ceolmund dump-chunk <world> <x> <y> [<dump file>]
+	                                        Write a chunk saved in a world as readable text.
+	ceolmund undump <dump file> <save file> Encode an edited dump back into a save file.
+	ceolmund undump-chunk <dump file> <world> <x> <y>
+	                                        Encode an edited chunk dump back into a chunk saved in a world.
+Options, given before the command:
+	--data-dir <folder>                     Keep settings, backups and exports in a folder instead of the default data folder.
+	--worlds-dir <folder>                   Keep worlds in a folder instead of the worlds folder in the data folder.
+	                                        Can be given more than once, new worlds are created in the first folder.
+The CEOLMUND_DATA_DIR and CEOLMUND_WORLDS_DIRS environment variables set the folders if the options are not given,
+CEOLMUND_WORLDS_DIRS is a list of folders separated in the same way as PATH.
+<world> can be the name of a world, the name of a folder in a worlds folder or the path to a world folder.
+Dumps are written to the standard output if no dump file is given. Chunk x and y are in chunks, not tiles.";
+
+/// Run a command given on the command line instead of starting the game.
+pub fn run_command(args: &[String], io: &IO) -> Result<(), String> {
+	let args: Vec<&str> = args.iter().map(String::as_str).collect();
+	match args.as_slice() {
+		["export", world, archive] => {
+			let world_filepath = find_world(world, io)?;
+			World::export(&world_filepath, &PathBuf::from(archive), io).map_err(|error| format!("Unable to export world: {error}"))?;
+			println!("Exported {} to {archive}.", world_filepath.display());
+			Ok(())
+		}
+		["import", archive] => {
+			let name = World::import(&PathBuf::from(archive), io).map_err(|error| format!("Unable to import world: {error}"))?;
+			println!("Imported {archive} as {name}.");
+			Ok(())
+		}
+		["upgrade", world] => {
+			let world_filepath = find_world(world, io)?;
+			let report = upgrade_world(&world_filepath, io).map_err(|error| format!("Unable to upgrade world: {error}"))?;
+			println!("Upgraded {}:", world_filepath.display());
+			println!("	Overview: from version {}.", report.overview_version);
+			match report.player_version {
+				Some(version) => println!("	Player: from version {version}."),
+				None => println!("	Player: no player file."),
+			}
+			println!("	Chunks moved into region files: {}.", report.chunk_files_migrated);
+			for (version, count) in report.chunks_upgraded.iter() {
+				println!("	Chunks upgraded from version {version}: {count}.");
+			}
+			println!("	Chunks already up to date: {}.", report.chunks_up_to_date);
+			for (chunk_pos, error) in report.failed_chunks.iter() {
+				println!("	Chunk {} {} could not be upgraded: {error}", chunk_pos[0], chunk_pos[1]);
+			}
+			match report.failed_chunks.is_empty() {
+				true => println!("	Unused namespace files deleted: {}.", report.namespaces_deleted),
+				false => println!("	Unused namespace files were kept as some chunks could not be upgraded."),
+			}
+			for namespace_hash in report.namespaces_kept.iter() {
+				println!("	Namespace file {namespace_hash:0>16x}.nsp was kept as quarantined chunks or an unloadable player file may use it.");
+			}
+			Ok(())
+		}
+		["dump", save_file, dump_file @ ..] if dump_file.len() <= 1 => {
+			let dump = save_dump::dump_file(&PathBuf::from(save_file), io).map_err(|error| format!("Unable to dump {save_file}: {error}"))?;
+			output_dump(&dump, dump_file.first().copied())
+		}
+		["dump-chunk", world, x, y, dump_file @ ..] if dump_file.len() <= 1 => {
+			let world_filepath = find_world(world, io)?;
+			let chunk_pos = parse_chunk_pos(x, y)?;
+			let dump = save_dump::dump_world_chunk(&world_filepath, chunk_pos, io).map_err(|error| format!("Unable to dump chunk {x} {y}: {error}"))?;
+			output_dump(&dump, dump_file.first().copied())
+		}
+		["undump", dump_file, save_file] => {
+			let dump = read_to_string(dump_file).map_err(|_| format!("Unable to read {dump_file}."))?;
+			save_dump::undump_file(&dump, &PathBuf::from(save_file), io).map_err(|error| format!("Unable to encode {dump_file}: {error}"))?;
+			println!("Encoded {dump_file} into {save_file}.");
+			Ok(())
+		}
+		["undump-chunk", dump_file, world, x, y] => {
+			let world_filepath = find_world(world, io)?;
+			let chunk_pos = parse_chunk_pos(x, y)?;
+			let dump = read_to_string(dump_file).map_err(|_| format!("Unable to read {dump_file}."))?;
+			save_dump::undump_world_chunk(&dump, &world_filepath, chunk_pos, io).map_err(|error| format!("Unable to encode {dump_file}: {error}"))?;
+			println!("Encoded {dump_file} into chunk {x} {y} of {}.", world_filepath.display());
+			Ok(())
+		}
+		["help"] | ["--help"] | ["-h"] => {
+			println!("{USAGE}");
+			Ok(())
+		}
+		_ => Err(USAGE.to_string()),
+	}
+}
+
+/// Take the options that set the data and worlds folders from the start of the command line arguments, getting the data folder and worlds folders given.
+pub fn take_data_path_options(args: &mut Vec<String>) -> Result<(Option<PathBuf>, Vec<PathBuf>), String> {
+	let mut data_path = None;
+	let mut worlds_paths = Vec::new();
+	loop {
+		match args.first().map(String::as_str) {
+			Some("--data-dir") => data_path = Some(take_option_path(args)?),
+			Some("--worlds-dir") => worlds_paths.push(take_option_path(args)?),
+			_ => return Ok((data_path, worlds_paths)),
+		}
+	}
+}
+
+/// Take an option and the path that follows it from the start of the command line arguments.
+fn take_option_path(args: &mut Vec<String>) -> Result<PathBuf, String> {
+	let option = args.remove(0);
+	match args.is_empty() {
+		true => Err(format!("{option} needs a folder.")),
+		false => Ok(PathBuf::from(args.remove(0))),
+	}
+}
+
+/// Get the folder of a world from a world name, a folder name in a worlds folder or a path.
+fn find_world(world: &str, io: &IO) -> Result<PathBuf, String> {
+	for worlds_path in &io.worlds_paths {
+		let in_worlds_folder = worlds_path.join(world);
+		if io.storage.is_folder(&in_worlds_folder) {
+			return Ok(in_worlds_folder);
+		}
+	}
+	if let Some((_, world_filepath)) = WorldList::new(io).worlds.into_iter().find(|(name, _)| name == world) {
+		return Ok(world_filepath);
+	}
+	let world_filepath = PathBuf::from(world);
+	match io.storage.is_folder(&world_filepath) {
+		true => Ok(world_filepath),
+		false => Err(format!("Cannot find world {world}.")),
+	}
+}
+
+/// Parse the x and y of a chunk.
+fn parse_chunk_pos(x: &str, y: &str) -> Result<[i64; 2], String> {
+	let x = x.parse().map_err(|_| format!("{x} is not a valid chunk x."))?;
+	let y = y.parse().map_err(|_| format!("{y} is not a valid chunk y."))?;
+	Ok([x, y])
+}
+
+/// Write a dump to a file or to the standard output if no file is given.
+fn output_dump(dump: &str, dump_file: Option<&str>) -> Result<(), String> {
+	match dump_file {
+		Some(dump_file) => {
+			write(dump_file, dump).map_err(|_| format!("Unable to write {dump_file}."))?;
+			println!("Dumped to {dump_file}.");
+			Ok(())
+		}
+		None => {
+			print!("{dump}");
+			Ok(())
+		}
+	}
+}
\ No newline at end of file
@@ -20,6 +20,23 @@ pub enum Error {
 	InvalidNamespaceName,
 	V0Error,
 	CannotReadToFile,
+	CannotWriteFile,
+	PaletteIndexOutOfBounds,
+	InvalidChunkRunLength,
+	ChecksumMismatch,
+	InvalidWorldArchive,
+	InvalidDump(String),
+	UnknownSaveFileType,
+	ChunkNotSaved,
+	InvalidDataLength,
+	UnknownName(&'static str, String),
+	MissingRecordField(String),
+	InvalidVarInt,
+	InvalidTile,
+	TooDeeplyNested,
+	InvalidFieldValue(&'static str),
+	NoDataFolder,
+	FileAlreadyExists,
 	
 	InvalidString,
 }
@@ -45,6 +62,23 @@ impl Display for Error {
 			Self::InvalidNamespaceName => write!(f, "Invalid namespace name."),
 			Self::V0Error => write!(f, "Version 0 Error."),
 			Self::CannotReadToFile => write!(f, "Cannot read to file."),
+			Self::CannotWriteFile => write!(f, "Cannot write file."),
+			Self::PaletteIndexOutOfBounds => write!(f, "Palette index is out of palette bounds."),
+			Self::InvalidChunkRunLength => write!(f, "Chunk tile stack runs do not cover the chunk."),
+			Self::ChecksumMismatch => write!(f, "File checksum does not match, the file is corrupted or truncated."),
+			Self::InvalidWorldArchive => write!(f, "Invalid world archive."),
+			Self::InvalidDump(problem) => write!(f, "Invalid dump: {problem}"),
+			Self::UnknownSaveFileType => write!(f, "Unknown type of save file, expected a .wld, .ent, .cnk or .nsp file."),
+			Self::ChunkNotSaved => write!(f, "The chunk has not been saved."),
+			Self::InvalidDataLength => write!(f, "The saved length of a tile or item does not match its data."),
+			Self::UnknownName(category, name) => write!(f, "Unknown {category} \"{name}\"."),
+			Self::MissingRecordField(name) => write!(f, "The field \"{name}\" is missing."),
+			Self::InvalidVarInt => write!(f, "Variable length integer is too long."),
+			Self::InvalidTile => write!(f, "Invalid tile."),
+			Self::TooDeeplyNested => write!(f, "Tiles and items are nested too deeply."),
+			Self::InvalidFieldValue(name) => write!(f, "The value of {name} is not valid."),
+			Self::NoDataFolder => write!(f, "Cannot find a folder to keep game data in, use --data-dir or set CEOLMUND_DATA_DIR."),
+			Self::FileAlreadyExists => write!(f, "There is already a file at that path."),
 		}
 	}
 }
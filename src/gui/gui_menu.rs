@@ -1,4 +1,4 @@
-use crate::{render::{vertex::Vertex}, io::{io::IO, game_key::GameKey}, world::{world::World, entity::entity_type::{EntityType, EntityVariant}, difficulty::Difficulty, item::{item::Item, crafting_recipes::CraftingRecipes}, tile::tile::Tile}};
+use crate::{render::{vertex::Vertex}, io::{io::IO, game_key::GameKey, backups::{list_backups, restore_backup, format_backup_time}, world_archive::{get_unused_archive_filepath, list_world_archives}}, world::{world::World, entity::entity_type::{EntityType, EntityVariant}, difficulty::Difficulty, item::{item::Item, crafting_recipes::CraftingRecipes}, tile::tile::Tile, direction::Direction4}};
 
 use super::{gui_alignment::GUIAlignment, gui_element::GUIElement, gui::GUI, gui_menu_variant::GUIMenuVariant, load_world_data::WorldList, gui_rect::GUIRect};
 
@@ -7,7 +7,7 @@ const RECT_BORDER_COLOR: [u8; 4] = [15, 15, 15, 255];
 const GRAYOUT_COLOR: [u8; 4] = [63, 63, 63, 127];
 const NO_COLOR: [u8; 4] = [0, 0, 0, 0];
 
-const SANDBOX_SPAWNABLE_ITEMS: [Item; 21] = [
+const SANDBOX_SPAWNABLE_ITEMS: [Item; 27] = [
 	Item::SandboxDestroyWand,
 	Item::Axe,
 	Item::Shovel,
@@ -18,6 +18,7 @@ const SANDBOX_SPAWNABLE_ITEMS: [Item; 21] = [
 	Item::Tile(Tile::Sand),
 	Item::Tile(Tile::BlackSand),
 	Item::Tile(Tile::Water),
+	Item::Tile(Tile::ShallowWater),
 	Item::Tile(Tile::Path),
 	Item::Tile(Tile::Flowers),
 	Item::Tile(Tile::FlowersRedYellow),
@@ -29,6 +30,11 @@ const SANDBOX_SPAWNABLE_ITEMS: [Item; 21] = [
 	Item::PineStick,
 	Item::OakStick,
 	Item::SharpendFlint,
+	Item::FishingRod,
+	Item::Boat,
+	Item::Tile(Tile::Door(false)),
+	Item::Tile(Tile::Gate(false)),
+	Item::Tile(Tile::OneWayPath(Direction4::North)),
 ];
 
 /// A GUI menu, these are stacked.
@@ -191,9 +197,11 @@ impl GUIMenu {
 					// Create world buttons
 					let world_count = load_world_data.worlds.len();
 					let mut buttons = Vec::new();
+					let mut manage_buttons = Vec::new();
 					for world_index in 0..world_count {
 						let world = load_world_data.worlds[world_index].clone();
-						buttons.push((world.0, GUIRect::new(0, world_index as i16 * 20, 146, 16), true));
+						buttons.push((world.0, GUIRect::new(0, world_index as i16 * 20, 100, 16), true));
+						manage_buttons.push(("Manage".to_string(), GUIRect::new(104, world_index as i16 * 20, 42, 16), true));
 					}
 					// Create the button group for the buttons with one function.
 					let buttons = GUIElement::SingleFunctionButtonGroup {
@@ -212,17 +220,35 @@ impl GUIMenu {
 							}
 						}),
 					};
+					// The buttons to open the manage world menu for each world.
+					let manage_buttons = GUIElement::SingleFunctionButtonGroup {
+						alignment: GUIAlignment::Center, buttons: manage_buttons,
+						click_mut_gui: (|_, gui, _, _, button_clicked_index| {
+							let top_menu = &gui.menus.last().unwrap().variant;
+							if let GUIMenuVariant::LoadWorld { world_list: load_world_data } = top_menu {
+								let (world_name, world_filepath) = load_world_data.worlds[button_clicked_index].clone();
+								gui.menus = vec![Self::new(GUIMenuVariant::ManageWorld { world_name, world_filepath })];
+							}
+						}),
+					};
 					// Vector with all elements.
 					vec![
 						GUIElement::RectContainer {
 							rect: GUIRect::new(51, 28, 154, 200), alignment: GUIAlignment::Center, inside_color: RECT_COLOR, border_color: RECT_BORDER_COLOR, inside_elements: vec![
 								GUIElement::Text { text: "Load World".to_string(), pos: [77, -20], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Center },
 								GUIElement::ScrollArea {
-									rect: GUIRect::new(0, 0, 150, 176), alignment: GUIAlignment::Center, border_color: RECT_BORDER_COLOR, inside_color: RECT_COLOR,
+									rect: GUIRect::new(0, 0, 150, 156), alignment: GUIAlignment::Center, border_color: RECT_BORDER_COLOR, inside_color: RECT_COLOR,
 									inside_height: (world_count as u16).saturating_mul(20).saturating_sub(4), scroll: 0, inside_elements: vec![
 										buttons,
+										manage_buttons,
 									],
 								},
+								GUIElement::Button {
+									rect: GUIRect::new(0, 160, 150, 16), alignment: GUIAlignment::Center, text: "Import World".to_string(), enabled: true,
+									click_mut_gui: (|_, gui, _, io| {
+										gui.menus = vec![Self::new(GUIMenuVariant::ImportWorld { archives: list_world_archives(&*io.storage, &io.exports_path) })];
+									}),
+								},
 								GUIElement::Button {
 									rect: GUIRect::new(0, 180, 150, 16), alignment: GUIAlignment::Center, text: "Cancel".to_string(), enabled: true,
 									click_mut_gui: (|_, gui, _, _| {
@@ -233,6 +259,199 @@ impl GUIMenu {
 						},
 					]
 				}
+				GUIMenuVariant::ImportWorld { archives } => {
+					// Create a button for each archive
+					let archive_count = archives.len();
+					let mut buttons = Vec::new();
+					for (archive_index, archive_filepath) in archives.iter().enumerate() {
+						let archive_name = archive_filepath.file_stem().and_then(|stem| stem.to_str()).unwrap_or("").to_string();
+						buttons.push((archive_name, GUIRect::new(0, archive_index as i16 * 20, 146, 16), true));
+					}
+					// Import a world when it's button is clicked.
+					let buttons = GUIElement::SingleFunctionButtonGroup {
+						alignment: GUIAlignment::Center, buttons,
+						click_mut_gui: (|_, gui, _, io, button_clicked_index| {
+							if let GUIMenuVariant::ImportWorld { archives } = &gui.menus.last().unwrap().variant {
+								match World::import(&archives[button_clicked_index], io) {
+									Ok(_) => gui.menus = vec![Self::new(GUIMenuVariant::LoadWorld { world_list: WorldList::new(io) })],
+									Err(error) => gui.menus.push(GUIMenu::new_error(format!("Unable to import world: {error}"))),
+								}
+							}
+						}),
+					};
+					vec![
+						GUIElement::RectContainer {
+							rect: GUIRect::new(51, 28, 154, 200), alignment: GUIAlignment::Center, inside_color: RECT_COLOR, border_color: RECT_BORDER_COLOR, inside_elements: vec![
+								GUIElement::Text { text: "Import World".to_string(), pos: [77, -20], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Center },
+								GUIElement::ScrollArea {
+									rect: GUIRect::new(0, 0, 150, 176), alignment: GUIAlignment::Center, border_color: RECT_BORDER_COLOR, inside_color: RECT_COLOR,
+									inside_height: (archive_count as u16).saturating_mul(20).saturating_sub(4), scroll: 0, inside_elements: vec![
+										buttons,
+									],
+								},
+								GUIElement::Button {
+									rect: GUIRect::new(0, 180, 150, 16), alignment: GUIAlignment::Center, text: "Cancel".to_string(), enabled: true,
+									click_mut_gui: (|_, gui, _, io| {
+										gui.menus = vec![Self::new(GUIMenuVariant::LoadWorld { world_list: WorldList::new(io) })];
+									}),
+								},
+							],
+						},
+					]
+				}
+				GUIMenuVariant::Backups { world_name, backups, .. } => {
+					// Create a button for each backup
+					let backup_count = backups.len();
+					let mut buttons = Vec::new();
+					for (backup_index, (time, _)) in backups.iter().enumerate() {
+						buttons.push((format_backup_time(*time), GUIRect::new(0, backup_index as i16 * 20, 146, 16), true));
+					}
+					// Restore a backup when it's button is clicked.
+					let buttons = GUIElement::SingleFunctionButtonGroup {
+						alignment: GUIAlignment::Center, buttons,
+						click_mut_gui: (|_, gui, _, io, button_clicked_index| {
+							let top_menu = &gui.menus.last().unwrap().variant;
+							if let GUIMenuVariant::Backups { world_filepath, backups, .. } = top_menu {
+								let world_filepath = world_filepath.clone();
+								let (_, backup_filepath) = &backups[button_clicked_index];
+								match restore_backup(&*io.storage, &io.backups_path, backup_filepath, &world_filepath) {
+									Ok(()) => gui.menus = vec![Self::new(GUIMenuVariant::LoadWorld { world_list: WorldList::new(io) })],
+									Err(error) => gui.menus.push(GUIMenu::new_error(format!("Unable to restore backup: {error}"))),
+								}
+							}
+						}),
+					};
+					vec![
+						GUIElement::RectContainer {
+							rect: GUIRect::new(51, 28, 154, 200), alignment: GUIAlignment::Center, inside_color: RECT_COLOR, border_color: RECT_BORDER_COLOR, inside_elements: vec![
+								GUIElement::Text { text: format!("Backups of {world_name}"), pos: [77, -20], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Center },
+								GUIElement::ScrollArea {
+									rect: GUIRect::new(0, 0, 150, 176), alignment: GUIAlignment::Center, border_color: RECT_BORDER_COLOR, inside_color: RECT_COLOR,
+									inside_height: (backup_count as u16).saturating_mul(20).saturating_sub(4), scroll: 0, inside_elements: vec![
+										buttons,
+									],
+								},
+								GUIElement::Button {
+									rect: GUIRect::new(0, 180, 150, 16), alignment: GUIAlignment::Center, text: "Cancel".to_string(), enabled: true,
+									click_mut_gui: (|_, gui, _, _| {
+										if let GUIMenuVariant::Backups { world_name, world_filepath, .. } = gui.menus.last().unwrap().variant.clone() {
+											gui.menus = vec![Self::new(GUIMenuVariant::ManageWorld { world_name, world_filepath })];
+										}
+									}),
+								},
+							],
+						},
+					]
+				}
+				GUIMenuVariant::ManageWorld { world_name, .. } => vec![
+					GUIElement::RectContainer {
+						rect: GUIRect::new(51, 28, 154, 200), alignment: GUIAlignment::Center, inside_color: RECT_COLOR, border_color: RECT_BORDER_COLOR, inside_elements: vec![
+							GUIElement::Text { text: format!("Manage {world_name}"), pos: [77, -20], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Center },
+							GUIElement::Text { text: "Name:".to_string(), pos: [0, 0], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Left },
+							GUIElement::TextEntry { text: world_name.clone(), rect: GUIRect::new(0, 20, 150, 16), alignment: GUIAlignment::Center, is_selected: false, text_length_limit: 20 },
+							GUIElement::Button {
+								rect: GUIRect::new(0, 40, 150, 16), alignment: GUIAlignment::Center, text: "Rename".to_string(), enabled: true,
+								click_mut_gui: (|_, gui, _, io| {
+									let top_menu = gui.menus.last().unwrap();
+									let world_filepath = match &top_menu.variant {
+										GUIMenuVariant::ManageWorld { world_filepath, .. } => world_filepath.clone(),
+										_ => return,
+									};
+									// Get the new name from the text entry.
+									let new_name = match &top_menu.elements[0] {
+										GUIElement::RectContainer { inside_elements, .. } => match &inside_elements[2] {
+											GUIElement::TextEntry { text, .. } => text.clone(),
+											_ => return,
+										}
+										_ => return,
+									};
+									if new_name.is_empty() {
+										gui.menus.push(GUIMenu::new_error("Invalid name.".to_string()));
+										return;
+									}
+									match World::rename(world_filepath, new_name.clone(), io) {
+										Ok(world_filepath) => gui.menus = vec![Self::new(GUIMenuVariant::ManageWorld { world_name: new_name, world_filepath })],
+										Err(error) => gui.menus.push(GUIMenu::new_error(format!("Unable to rename world: {error}"))),
+									}
+								}),
+							},
+							GUIElement::Button {
+								rect: GUIRect::new(0, 60, 150, 16), alignment: GUIAlignment::Center, text: "Duplicate".to_string(), enabled: true,
+								click_mut_gui: (|_, gui, _, io| {
+									if let GUIMenuVariant::ManageWorld { world_filepath, .. } = &gui.menus.last().unwrap().variant {
+										match World::duplicate(world_filepath, io) {
+											Ok(()) => gui.menus = vec![Self::new(GUIMenuVariant::LoadWorld { world_list: WorldList::new(io) })],
+											Err(error) => gui.menus.push(GUIMenu::new_error(format!("Unable to duplicate world: {error}"))),
+										}
+									}
+								}),
+							},
+							GUIElement::Button {
+								rect: GUIRect::new(0, 80, 150, 16), alignment: GUIAlignment::Center, text: "Backups".to_string(), enabled: true,
+								click_mut_gui: (|_, gui, _, io| {
+									if let GUIMenuVariant::ManageWorld { world_name, world_filepath } = gui.menus.last().unwrap().variant.clone() {
+										let backups = list_backups(&*io.storage, &io.backups_path, &world_filepath);
+										gui.menus = vec![Self::new(GUIMenuVariant::Backups { world_name, world_filepath, backups })];
+									}
+								}),
+							},
+							GUIElement::Button {
+								rect: GUIRect::new(0, 100, 150, 16), alignment: GUIAlignment::Center, text: "Export".to_string(), enabled: true,
+								click_mut_gui: (|_, gui, _, io| {
+									if let GUIMenuVariant::ManageWorld { world_filepath, .. } = &gui.menus.last().unwrap().variant {
+										let world_folder_name = world_filepath.file_name().and_then(|file_name| file_name.to_str()).unwrap_or("world");
+										let archive_filepath = get_unused_archive_filepath(&*io.storage, &io.exports_path, world_folder_name);
+										let archive_name = archive_filepath.file_name().and_then(|file_name| file_name.to_str()).unwrap_or_default().to_string();
+										match World::export(world_filepath, &archive_filepath, io) {
+											Ok(()) => gui.menus.push(GUIMenu::new_message("World Exported".to_string(), format!("Saved to exports/{archive_name}"))),
+											Err(error) => gui.menus.push(GUIMenu::new_error(format!("Unable to export world: {error}"))),
+										}
+									}
+								}),
+							},
+							GUIElement::Button {
+								rect: GUIRect::new(0, 120, 150, 16), alignment: GUIAlignment::Center, text: "Delete".to_string(), enabled: true,
+								click_mut_gui: (|_, gui, _, _| {
+									if let GUIMenuVariant::ManageWorld { world_name, world_filepath } = gui.menus.last().unwrap().variant.clone() {
+										gui.menus.push(Self::new(GUIMenuVariant::DeleteWorld { world_name, world_filepath }));
+									}
+								}),
+							},
+							GUIElement::Button {
+								rect: GUIRect::new(0, 180, 150, 16), alignment: GUIAlignment::Center, text: "Back".to_string(), enabled: true,
+								click_mut_gui: (|_, gui, _, io| {
+									gui.menus = vec![Self::new(GUIMenuVariant::LoadWorld { world_list: WorldList::new(io) })];
+								}),
+							},
+						],
+					},
+				],
+				GUIMenuVariant::DeleteWorld { world_name, .. } => vec![
+					GUIElement::Grayout { color: GRAYOUT_COLOR },
+					GUIElement::RectContainer {
+						rect: GUIRect::new(51, 88, 154, 80), alignment: GUIAlignment::Center, inside_color: RECT_COLOR, border_color: RECT_BORDER_COLOR, inside_elements: vec![
+							GUIElement::Text { text: "Delete World".to_string(), pos: [77, -20], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Center },
+							GUIElement::Text { text: format!("Delete {world_name}?"), pos: [77, 8], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Center },
+							GUIElement::Button {
+								rect: GUIRect::new(0, 40, 150, 16), alignment: GUIAlignment::Center, text: "Delete".to_string(), enabled: true,
+								click_mut_gui: (|_, gui, _, io| {
+									if let GUIMenuVariant::DeleteWorld { world_filepath, .. } = &gui.menus.last().unwrap().variant {
+										match World::delete(world_filepath, io) {
+											Ok(()) => gui.menus = vec![Self::new(GUIMenuVariant::LoadWorld { world_list: WorldList::new(io) })],
+											Err(error) => gui.menus.push(GUIMenu::new_error(format!("Unable to delete world: {error}"))),
+										}
+									}
+								}),
+							},
+							GUIElement::Button {
+								rect: GUIRect::new(0, 60, 150, 16), alignment: GUIAlignment::Center, text: "Cancel".to_string(), enabled: true,
+								click_mut_gui: (|_, gui, _, _| {
+									gui.menus.pop();
+								}),
+							},
+						],
+					},
+				],
 				GUIMenuVariant::Paused => vec![
 					GUIElement::RectContainer {
 						rect: GUIRect::new(51, 28, 154, 200), alignment: GUIAlignment::Center, inside_color: RECT_COLOR, border_color: RECT_BORDER_COLOR, inside_elements: vec![
@@ -241,6 +460,14 @@ impl GUIMenu {
 								rect: GUIRect::new(0, 0, 150, 16), alignment: GUIAlignment::Center, text: "Resume".to_string(), enabled: true,
 								click_mut_gui: (|_, gui, _, _| {gui.menus.pop();}),
 							},
+							GUIElement::Button {
+								rect: GUIRect::new(0, 20, 150, 16), alignment: GUIAlignment::Center, text: "Save".to_string(), enabled: true,
+								click_mut_gui: (|_, _, world, io| {
+									if let Some(world) = world {
+										world.save(io);
+									}
+								}),
+							},
 							GUIElement::Button {
 								rect: GUIRect::new(0, 160, 150, 16), alignment: GUIAlignment::Center, text: "Exit to Title".to_string(), enabled: true,
 								click_mut_gui: (|_, gui, world, _| {
@@ -271,20 +498,8 @@ impl GUIMenu {
 						],
 					},
 				],
-				GUIMenuVariant::Error => vec![
-					GUIElement::Grayout { color: GRAYOUT_COLOR },
-					GUIElement::RectContainer {
-						rect: GUIRect::new(51, 88, 154, 80), alignment: GUIAlignment::Center, inside_color: RECT_COLOR, border_color: RECT_BORDER_COLOR, inside_elements: vec![
-							GUIElement::Text { text: "Error".to_string(), pos: [77, -20], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Center },
-							GUIElement::Button {
-								rect: GUIRect::new(0, 60, 150, 16), alignment: GUIAlignment::Center, text: "OK".to_string(), enabled: true,
-								click_mut_gui: (|_, gui, _, _| {
-									gui.menus.pop();
-								}),
-							},
-						],
-					},
-				],
+				GUIMenuVariant::Error => Self::get_message_box_elements("Error".to_string()),
+				GUIMenuVariant::Message(title) => Self::get_message_box_elements(title),
 				GUIMenuVariant::IngameHUD => Vec::new(),
 				GUIMenuVariant::SpawnItems => {
 					// Grid elements.
@@ -486,6 +701,41 @@ impl GUIMenu {
 						rect: GUIRect::new(256 - 202 - 2, 2, 202, 8), alignment: GUIAlignment::Right, inside_color: [255, 0, 0, 255], border_color: [0, 0, 0, 255],
 						progress: player.health, max_progress: EntityVariant::Player.max_health(),
 					});
+					// Stamina bar
+					let stamina = match &player.entity_type {
+						EntityType::Player { stamina, .. } => *stamina,
+					};
+					out.push(GUIElement::ProgressBar {
+						rect: GUIRect::new(256 - 202 - 2, 12, 202, 8), alignment: GUIAlignment::Right, inside_color: [255, 223, 63, 255], border_color: [0, 0, 0, 255],
+						progress: stamina as u32, max_progress: EntityVariant::Player.max_stamina() as u32,
+					});
+				}
+				// Warnings
+				for (warning_index, (warning, _)) in world.warnings.iter().rev().enumerate() {
+					out.push(GUIElement::Text { text: warning.clone(), pos: [127, 236 - warning_index as i16 * 12], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Center });
+				}
+				out
+			}
+			GUIMenuVariant::ExitingGame | GUIMenuVariant::ExitingToTitle => {
+				let mut out = Vec::new();
+				let world = match world {
+					Some(world) => world,
+					None => return out,
+				};
+				// Warnings
+				for (warning_index, (warning, _)) in world.warnings.iter().rev().enumerate() {
+					out.push(GUIElement::Text { text: warning.clone(), pos: [127, 236 - warning_index as i16 * 12], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Center });
+				}
+				// Allow closing the world without saving if saving keeps failing.
+				if world.has_save_failed() {
+					out.push(GUIElement::Button {
+						rect: GUIRect::new(53, 156, 150, 16), alignment: GUIAlignment::Center, text: "Close Without Saving".to_string(), enabled: true,
+						click_mut_gui: (|_, _, world, _| {
+							if let Some(world) = world {
+								world.is_freed = true;
+							}
+						}),
+					});
 				}
 				out
 			}
@@ -511,7 +761,7 @@ impl GUIMenu {
 	pub fn does_menu_pause_game(&self) -> bool {
 		match self.variant {
 			GUIMenuVariant::Test | GUIMenuVariant::Paused | GUIMenuVariant::ExitingGame | GUIMenuVariant::ExitingToTitle | GUIMenuVariant::Crafting(..) |
-			GUIMenuVariant::Title | GUIMenuVariant::CreateWorld | GUIMenuVariant::Error | GUIMenuVariant::LoadWorld { .. } | GUIMenuVariant::SpawnItems => true,
+			GUIMenuVariant::Title | GUIMenuVariant::CreateWorld | GUIMenuVariant::Error | GUIMenuVariant::Message(..) | GUIMenuVariant::LoadWorld { .. } | GUIMenuVariant::Backups { .. } | GUIMenuVariant::ManageWorld { .. } | GUIMenuVariant::DeleteWorld { .. } | GUIMenuVariant::ImportWorld { .. } | GUIMenuVariant::SpawnItems => true,
 			GUIMenuVariant::IngameHUD => false,
 		}
 	}
@@ -608,6 +858,31 @@ impl GUIMenu {
 		}
 	}
 
+	/// Get the elements of a message box with a title and an OK button to close it.
+	fn get_message_box_elements(title: String) -> Vec<GUIElement> {
+		vec![
+			GUIElement::Grayout { color: GRAYOUT_COLOR },
+			GUIElement::RectContainer {
+				rect: GUIRect::new(51, 88, 154, 80), alignment: GUIAlignment::Center, inside_color: RECT_COLOR, border_color: RECT_BORDER_COLOR, inside_elements: vec![
+					GUIElement::Text { text: title, pos: [77, -20], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Center },
+					GUIElement::Button {
+						rect: GUIRect::new(0, 60, 150, 16), alignment: GUIAlignment::Center, text: "OK".to_string(), enabled: true,
+						click_mut_gui: (|_, gui, _, _| {
+							gui.menus.pop();
+						}),
+					},
+				],
+			},
+		]
+	}
+
+	/// Create a GUI menu that shows a message with a title.
+	pub fn new_message(title: String, message: String) -> Self {
+		let mut out = Self::new(GUIMenuVariant::Message(title));
+		out.elements.push(GUIElement::Text { text: message, pos: [127, 116], alignment: GUIAlignment::Center, text_alignment: GUIAlignment::Center });
+		out
+	}
+
 	/// Create a error GUI menu from a string.
 	pub fn new_error(error: String) -> Self {
 		let mut out = Self::new(GUIMenuVariant::Error);
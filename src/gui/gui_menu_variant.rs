@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use crate::world::item::crafting_recipes::CraftingRecipes;
 
 use super::load_world_data::WorldList;
@@ -12,7 +14,12 @@ pub enum GUIMenuVariant {
 	IngameHUD,
 	CreateWorld,
 	Error,
+	Message(String),
 	LoadWorld { world_list: WorldList },
+	ManageWorld { world_name: String, world_filepath: PathBuf },
+	DeleteWorld { world_name: String, world_filepath: PathBuf },
+	ImportWorld { archives: Vec<PathBuf> },
+	Backups { world_name: String, world_filepath: PathBuf, backups: Vec<(u64, PathBuf)> },
 	SpawnItems,
 	Crafting(CraftingRecipes),
 }
\ No newline at end of file
@@ -2,7 +2,7 @@ use std::path::PathBuf;
 
 use crate::{io::io::IO, world::world::World};
 
-/// A struct containing the name and path of all the worlds in the users world folder.
+/// A struct containing the name and path of all the worlds in the users worlds folders.
 #[derive(Clone)]
 pub struct WorldList {
 	pub worlds: Vec<(String, PathBuf)>,
@@ -12,15 +12,11 @@ impl WorldList {
 	/// Get a vector of all valid world name and filepath pairs.
 	pub fn new(io: &IO) -> Self {
 		let mut out = Vec::new();
-		// For each valid item in the worlds path.
-		for item in io.worlds_path.read_dir().unwrap() {
-			if let Ok(item) = item {
-				// Get the path
-				let filepath = item.path();
-				// Add it to the list if it is a valid world.
-				if let Ok(basic_world) = World::load(filepath.clone(), io, true) {
-					out.push((basic_world.name, filepath));
-				}
+		// For each item in the worlds folders, skipping folders that cannot be read.
+		for filepath in io.worlds_paths.iter().flat_map(|worlds_path| io.storage.list_folder(worlds_path).unwrap_or_default()) {
+			// Add it to the list if it is a valid world.
+			if let Ok(basic_world) = World::load(filepath.clone(), io, true) {
+				out.push((basic_world.name, filepath));
 			}
 		}
 		
@@ -0,0 +1,272 @@
+use std::{path::{PathBuf, Path}, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{error::Error, world::world_overview::WorldOverview};
+
+use super::{world_archive::{pack_world_folder, unpack_world_archive}, file_reader::FileReader, storage::Storage};
+
+/// How many backups of each world to keep, the oldest backups are deleted when there are more.
+pub const BACKUPS_TO_KEEP: usize = 5;
+
+/// Get the ID of a world from it's overview, if the world was saved with one and the overview can be read.
+fn get_world_id(storage: &dyn Storage, world_filepath: &Path) -> Option<u64> {
+	WorldOverview::load(storage, &world_filepath.join("overview.wld"), &world_filepath.join("namespaces")).ok()?.world_id
+}
+
+/// Get the folder the backups of a world are kept in.
+/// The folder is named after the world folder followed by the world ID and is found by the ID, so the backups stay with the world when it is renamed or moved and worlds with the same folder name keep their backups apart.
+/// Worlds without an ID or with an overview that cannot be read use the folder named only after the world folder if there is one, otherwise the folder of the only world with that folder name that has backups.
+pub fn get_world_backups_filepath(storage: &dyn Storage, backups_filepath: &Path, world_filepath: &Path) -> Option<PathBuf> {
+	let world_folder_name = world_filepath.file_name()?.to_string_lossy();
+	let world_backups_filepaths: Vec<PathBuf> = storage.list_folder(backups_filepath).unwrap_or_default().into_iter().filter(|filepath| storage.is_folder(filepath)).collect();
+	let get_folder_name = |filepath: &PathBuf| filepath.file_name().map(|folder_name| folder_name.to_string_lossy().into_owned()).unwrap_or_default();
+	match get_world_id(storage, world_filepath) {
+		Some(world_id) => {
+			let world_id_suffix = format!(" {world_id:0>16x}");
+			match world_backups_filepaths.into_iter().find(|filepath| get_folder_name(filepath).ends_with(&world_id_suffix)) {
+				Some(world_backups_filepath) => Some(world_backups_filepath),
+				None => Some(backups_filepath.join(format!("{world_folder_name}{world_id_suffix}"))),
+			}
+		}
+		None => {
+			let unkeyed_backups_filepath = backups_filepath.join(world_filepath.file_name()?);
+			let keyed_backups_filepaths: Vec<PathBuf> = world_backups_filepaths.into_iter().filter(|filepath| {
+				let folder_name = get_folder_name(filepath);
+				match folder_name.strip_prefix(world_folder_name.as_ref()).and_then(|suffix| suffix.strip_prefix(' ')) {
+					Some(world_id) => world_id.len() == 16 && u64::from_str_radix(world_id, 16).is_ok(),
+					None => false,
+				}
+			}).collect();
+			match (storage.exists(&unkeyed_backups_filepath), keyed_backups_filepaths.as_slice()) {
+				(false, [world_backups_filepath]) => Some(world_backups_filepath.clone()),
+				_ => Some(unkeyed_backups_filepath),
+			}
+		}
+	}
+}
+
+/// Move backups kept in a folder named only after the world folder, as they were before worlds had IDs, into the backups folder of a world.
+/// The backups go to the first world with that folder name that makes or lists backups.
+fn move_unkeyed_backups(storage: &dyn Storage, backups_filepath: &Path, world_filepath: &Path, world_backups_filepath: &Path) -> Result<(), Error> {
+	let unkeyed_backups_filepath = match world_filepath.file_name() {
+		Some(world_folder_name) => backups_filepath.join(world_folder_name),
+		None => return Ok(()),
+	};
+	match storage.is_folder(&unkeyed_backups_filepath) && !storage.exists(world_backups_filepath) {
+		true => storage.rename(&unkeyed_backups_filepath, world_backups_filepath),
+		false => Ok(()),
+	}
+}
+
+/// Save a backup of a world folder as a timestamped archive, deleting the oldest backups of the world if there are more than BACKUPS_TO_KEEP.
+/// If there is already a backup made in the same second then a number is added after the time so the backups do not overwrite each other.
+pub fn create_backup(storage: &dyn Storage, backups_filepath: &Path, world_filepath: &Path) -> Result<(), Error> {
+	let world_backups_filepath = get_world_backups_filepath(storage, backups_filepath, world_filepath).ok_or(Error::CannotCreateFolder)?;
+	move_unkeyed_backups(storage, backups_filepath, world_filepath, &world_backups_filepath)?;
+	if !storage.exists(&world_backups_filepath) {
+		storage.create_folder(&world_backups_filepath)?;
+	}
+	// Write the backup
+	let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+	let mut backup_filepath = world_backups_filepath.join(format!("{time}.bkp"));
+	let mut number = 2;
+	while storage.exists(&backup_filepath) {
+		backup_filepath = world_backups_filepath.join(format!("{time} {number}.bkp"));
+		number += 1;
+	}
+	let file = pack_world_folder(storage, world_filepath)?;
+	file.write(storage, &backup_filepath)?;
+	// Delete old backups
+	for (_, backup_filepath) in list_backups(storage, backups_filepath, world_filepath).iter().skip(BACKUPS_TO_KEEP) {
+		storage.remove_file(backup_filepath)?;
+	}
+	Ok(())
+}
+
+/// Get the time each backup of a world was made at and the path to it, newest first.
+pub fn list_backups(storage: &dyn Storage, backups_filepath: &Path, world_filepath: &Path) -> Vec<(u64, PathBuf)> {
+	let mut out = Vec::new();
+	let world_backups_filepath = match get_world_backups_filepath(storage, backups_filepath, world_filepath) {
+		Some(world_backups_filepath) => world_backups_filepath,
+		None => return out,
+	};
+	move_unkeyed_backups(storage, backups_filepath, world_filepath, &world_backups_filepath).ok();
+	let item_filepaths = match storage.list_folder(&world_backups_filepath) {
+		Ok(item_filepaths) => item_filepaths,
+		Err(_) => return out,
+	};
+	// Get the time and number of each backup, backups made in the same second are ordered by the number after the time
+	let mut backups = Vec::new();
+	for backup_filepath in item_filepaths {
+		if backup_filepath.extension().and_then(|extension| extension.to_str()) != Some("bkp") {
+			continue;
+		}
+		if let Some((time, number)) = backup_filepath.file_stem().and_then(|stem| stem.to_str()).and_then(parse_backup_name) {
+			backups.push((time, number, backup_filepath));
+		}
+	}
+	backups.sort_by(|(time_a, number_a, _), (time_b, number_b, _)| (time_b, number_b).cmp(&(time_a, number_a)));
+	out.extend(backups.into_iter().map(|(time, _, backup_filepath)| (time, backup_filepath)));
+	out
+}
+
+/// Parse the "time" or "time number" name of a backup file into the time and number, backups without a number are the first backup made in that second.
+fn parse_backup_name(name: &str) -> Option<(u64, u32)> {
+	match name.split_once(' ') {
+		Some((time, number)) => Some((time.parse().ok()?, number.parse().ok()?)),
+		None => Some((name.parse().ok()?, 1)),
+	}
+}
+
+/// Replace a world folder with the contents of a backup, backing up the world first so the restore can be undone.
+/// The backup is unpacked next to the world folder first so the world is left untouched if the backup cannot be read.
+pub fn restore_backup(storage: &dyn Storage, backups_filepath: &Path, backup_filepath: &Path, world_filepath: &Path) -> Result<(), Error> {
+	// Read the backup before backing up the world as that may delete the backup being restored
+	let (mut file, _is_version_0) = FileReader::read_from_file(storage, backup_filepath)?;
+	create_backup(storage, backups_filepath, world_filepath)?;
+	// Unpack backup
+	let mut restoring_filepath = world_filepath.as_os_str().to_os_string();
+	restoring_filepath.push(".restoring");
+	let restoring_filepath = PathBuf::from(restoring_filepath);
+	if storage.exists(&restoring_filepath) {
+		storage.remove_folder(&restoring_filepath)?;
+	}
+	if let Err(error) = unpack_world_archive(storage, &mut file, &restoring_filepath) {
+		storage.remove_folder(&restoring_filepath).ok();
+		return Err(error);
+	}
+	// Swap the world folder with the unpacked backup
+	let mut old_filepath = world_filepath.as_os_str().to_os_string();
+	old_filepath.push(".old");
+	let old_filepath = PathBuf::from(old_filepath);
+	if storage.exists(&old_filepath) {
+		storage.remove_folder(&old_filepath)?;
+	}
+	let has_world = storage.exists(world_filepath);
+	if has_world {
+		storage.rename(world_filepath, &old_filepath)?;
+	}
+	// Put the world back if the unpacked backup cannot be moved into place
+	if let Err(error) = storage.rename(&restoring_filepath, world_filepath) {
+		if has_world {
+			storage.rename(&old_filepath, world_filepath)?;
+		}
+		storage.remove_folder(&restoring_filepath).ok();
+		return Err(error);
+	}
+	storage.remove_folder(&old_filepath).ok();
+	Ok(())
+}
+
+/// Get a "YYYY-MM-DD HH:MM" UTC date and time string from a unix time in seconds.
+pub fn format_backup_time(time: u64) -> String {
+	let days = (time / 86400) as i64;
+	let seconds_in_day = time % 86400;
+	// Convert days since 1970-01-01 to a date
+	let shifted_days = days + 719468;
+	let era = shifted_days.div_euclid(146097);
+	let day_of_era = shifted_days.rem_euclid(146097);
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let shifted_month = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+	let month = match shifted_month < 10 {
+		true => shifted_month + 3,
+		false => shifted_month - 9,
+	};
+	let year = year_of_era + era * 400 + (month <= 2) as i64;
+	format!("{year:0>4}-{month:0>2}-{day:0>2} {:0>2}:{:0>2}", seconds_in_day / 3600, seconds_in_day % 3600 / 60)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{path::{Path, PathBuf}, sync::Arc};
+
+	use crate::{io::{data_paths::DataPaths, io::{IO, SERIALIZATION_VERSION}, storage::MemoryStorage}, world::{difficulty::Difficulty, world::World, world_overview::WorldOverview}};
+
+	use super::{create_backup, get_world_backups_filepath, list_backups};
+
+	fn create_io() -> IO {
+		let data_paths = DataPaths { data_path: PathBuf::from("data"), worlds_paths: vec![PathBuf::from("worlds a"), PathBuf::from("worlds b")] };
+		IO::with_storage(Arc::new(MemoryStorage::new()), data_paths)
+	}
+
+	/// Create a world in a worlds folder with an overview holding an ID.
+	fn create_world(io: &IO, worlds_path: &Path, name: &str, world_id: Option<u64>) -> PathBuf {
+		let world_filepath = worlds_path.join(name);
+		io.storage.create_folder(&world_filepath.join("namespaces")).unwrap();
+		io.namespace.write(&*io.storage, &world_filepath.join("namespaces").join(format!("{:0>16x}.nsp", io.namespace_hash))).unwrap();
+		let overview = WorldOverview { name: name.to_string(), seed: 1, difficulty: Difficulty::Easy, time: 0, generator_version: 0, world_id, version: SERIALIZATION_VERSION };
+		overview.serialize(io.namespace_hash).write(&*io.storage, &world_filepath.join("overview.wld")).unwrap();
+		world_filepath
+	}
+
+	#[test]
+	fn worlds_with_the_same_folder_name_keep_their_own_backups() {
+		let io = create_io();
+		let world_a = create_world(&io, Path::new("worlds a"), "world", Some(1));
+		let world_b = create_world(&io, Path::new("worlds b"), "world", Some(2));
+		create_backup(&*io.storage, &io.backups_path, &world_a).unwrap();
+		create_backup(&*io.storage, &io.backups_path, &world_a).unwrap();
+		create_backup(&*io.storage, &io.backups_path, &world_b).unwrap();
+		assert_ne!(get_world_backups_filepath(&*io.storage, &io.backups_path, &world_a), get_world_backups_filepath(&*io.storage, &io.backups_path, &world_b));
+		assert_eq!(list_backups(&*io.storage, &io.backups_path, &world_a).len(), 2);
+		assert_eq!(list_backups(&*io.storage, &io.backups_path, &world_b).len(), 1);
+	}
+
+	#[test]
+	fn backups_kept_by_world_folder_name_are_moved_to_the_world() {
+		let io = create_io();
+		let world = create_world(&io, Path::new("worlds a"), "world", Some(1));
+		let unkeyed_backups_filepath = io.backups_path.join("world");
+		io.storage.create_folder(&unkeyed_backups_filepath).unwrap();
+		io.storage.write(&unkeyed_backups_filepath.join("100.bkp"), &[]).unwrap();
+		let backups = list_backups(&*io.storage, &io.backups_path, &world);
+		assert_eq!(backups.iter().map(|(time, _)| *time).collect::<Vec<_>>(), [100]);
+		assert!(!io.storage.exists(&unkeyed_backups_filepath));
+	}
+
+	#[test]
+	fn backups_stay_with_worlds_moved_to_another_worlds_folder() {
+		let io = create_io();
+		let world = create_world(&io, Path::new("worlds a"), "world", Some(1));
+		create_backup(&*io.storage, &io.backups_path, &world).unwrap();
+		let moved_world = Path::new("worlds b").join("moved world");
+		io.storage.rename(&world, &moved_world).unwrap();
+		assert_eq!(list_backups(&*io.storage, &io.backups_path, &moved_world).len(), 1);
+	}
+
+	#[test]
+	fn worlds_without_an_id_use_backups_kept_by_world_folder_name() {
+		let io = create_io();
+		// Worlds saved without an ID use the folder named after the world folder
+		let world = create_world(&io, Path::new("worlds a"), "world", None);
+		create_backup(&*io.storage, &io.backups_path, &world).unwrap();
+		assert!(io.storage.exists(&io.backups_path.join("world")));
+		// Once the world has an ID the backups are moved to the folder for the ID
+		let world = create_world(&io, Path::new("worlds b"), "world", Some(1));
+		assert_eq!(list_backups(&*io.storage, &io.backups_path, &world).len(), 1);
+		assert!(!io.storage.exists(&io.backups_path.join("world")));
+		// A world with an overview that cannot be read still finds the backups by the folder name
+		io.storage.write(&world.join("overview.wld"), &[1, 2, 3]).unwrap();
+		assert_eq!(list_backups(&*io.storage, &io.backups_path, &world).len(), 1);
+	}
+
+	#[test]
+	fn backups_move_with_renamed_worlds() {
+		let io = create_io();
+		let world = World::new(1, "Old Name".to_string(), &io, Difficulty::Easy).unwrap().filepath;
+		assert_eq!(list_backups(&*io.storage, &io.backups_path, &world).len(), 1);
+		let renamed_world = World::rename(world.clone(), "New Name".to_string(), &io).unwrap();
+		assert_ne!(renamed_world, world);
+		assert_eq!(list_backups(&*io.storage, &io.backups_path, &renamed_world).len(), 1);
+	}
+
+	#[test]
+	fn duplicated_worlds_do_not_share_backups() {
+		let io = create_io();
+		let world = World::new(1, "World".to_string(), &io, Difficulty::Easy).unwrap().filepath;
+		World::duplicate(&world, &io).unwrap();
+		assert_eq!(list_backups(&*io.storage, &io.backups_path, &world).len(), 1);
+		assert!(list_backups(&*io.storage, &io.backups_path, &Path::new("worlds a").join("World Copy")).is_empty());
+	}
+}
\ No newline at end of file
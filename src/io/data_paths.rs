@@ -0,0 +1,73 @@
+use std::{env::{var_os, split_paths}, path::PathBuf};
+#[cfg(target_os = "linux")]
+use std::fs::{create_dir_all, rename};
+
+use home::home_dir;
+
+use crate::error::Error;
+
+/// The environment variable that sets the folder the game keeps it's data in.
+pub const DATA_DIR_VARIABLE: &str = "CEOLMUND_DATA_DIR";
+/// The environment variable that sets the folders worlds are kept in, as a list of paths separated like the PATH variable.
+pub const WORLDS_DIRS_VARIABLE: &str = "CEOLMUND_WORLDS_DIRS";
+
+/// The folders the game keeps it's data and worlds in.
+pub struct DataPaths {
+	pub data_path: PathBuf, // Backups, exports and settings are kept in this folder.
+	pub worlds_paths: Vec<PathBuf>, // Folders that worlds are kept in, new worlds are created in the first folder.
+}
+
+impl DataPaths {
+	/// Get the data paths for a data folder with it's worlds folder as the only worlds folder.
+	pub fn from_data_path(data_path: PathBuf) -> Self {
+		Self {
+			worlds_paths: vec![data_path.join("worlds")],
+			data_path,
+		}
+	}
+
+	/// Get the data paths from paths given on the command line, then the environment variables, then the default data folder for the platform.
+	/// The worlds folders are the worlds folder in the data folder unless other worlds folders are given.
+	pub fn new(data_path: Option<PathBuf>, worlds_paths: Vec<PathBuf>) -> Result<Self, Error> {
+		let data_path = match data_path.or_else(|| var_os(DATA_DIR_VARIABLE).filter(|data_path| !data_path.is_empty()).map(PathBuf::from)) {
+			Some(data_path) => data_path,
+			None => get_default_data_path()?,
+		};
+		let mut out = Self::from_data_path(data_path);
+		let worlds_paths = match worlds_paths.is_empty() {
+			true => var_os(WORLDS_DIRS_VARIABLE).map(|worlds_paths| split_paths(&worlds_paths).filter(|worlds_path| !worlds_path.as_os_str().is_empty()).collect()).unwrap_or_default(),
+			false => worlds_paths,
+		};
+		if !worlds_paths.is_empty() {
+			out.worlds_paths = worlds_paths;
+		}
+		Ok(out)
+	}
+}
+
+/// On Linux data is kept in the XDG data folder. Data kept in the .ceolmund folder in the home folder by older versions is moved there.
+/// If the old data folder cannot be moved, for example because the new folder is on another drive, then the old data folder is used.
+#[cfg(target_os = "linux")]
+fn get_default_data_path() -> Result<PathBuf, Error> {
+	let home_path = home_dir();
+	// Use XDG_DATA_HOME if it is set to an absolute path, otherwise use the default of ~/.local/share
+	let data_home_path = match var_os("XDG_DATA_HOME").map(PathBuf::from) {
+		Some(data_home_path) if data_home_path.is_absolute() => data_home_path,
+		_ => home_path.as_ref().ok_or(Error::NoDataFolder)?.join(".local").join("share"),
+	};
+	let data_path = data_home_path.join("ceolmund");
+	// Move the old data folder if there is one and nothing is in the new data folder
+	match home_path.map(|home_path| home_path.join(".ceolmund")) {
+		Some(old_data_path) if old_data_path.is_dir() && !data_path.exists() => match create_dir_all(&data_home_path).and_then(|_| rename(&old_data_path, &data_path)) {
+			Ok(()) => Ok(data_path),
+			Err(_) => Ok(old_data_path),
+		},
+		_ => Ok(data_path),
+	}
+}
+
+/// On other platforms data is kept in the .ceolmund folder in the home folder.
+#[cfg(not(target_os = "linux"))]
+fn get_default_data_path() -> Result<PathBuf, Error> {
+	Ok(home_dir().ok_or(Error::NoDataFolder)?.join(".ceolmund"))
+}
\ No newline at end of file
@@ -1,33 +1,53 @@
 use std::ffi::CStr;
-use std::path::PathBuf;
+use std::path::Path;
 
-use std::fs::{read, remove_file, rename};
+use crc64::crc64;
 
 use crate::error::Error;
 
+use super::{file_writer::CHECKSUM_MAGIC, storage::Storage};
+
+/// How deeply tiles and items can be nested inside each other, so that corrupt files can not overflow the stack.
+const MAX_NESTING_DEPTH: u8 = 32;
+
 /// For reading a file structure that allows for a file version, a body consisting of an array of u8 values and an array of strings.
 pub struct FileReader {
 	pub data: Vec<u8>, // The content of the file.
 	pub read_index: usize, // Where we have read to, advances each time we read data.
 	strings_v0: Vec<u8>, // Legacy for reading version 0 files
+	nesting_depth: u8, // How many tiles and items we are reading inside of.
 }
 
-/// Load a file reader from disk.
+/// Load a file reader from a file in a storage.
 impl FileReader {
-	pub fn read_from_file(path: &PathBuf) -> Result<(Self, bool), Error> { // Object and if it is a version 0 file.
-		// Restore backup in case of file save error
-		let mut backup_path = path.clone();
-		backup_path.push(".bak");
-		if backup_path.exists() {
-			if path.exists() {
-				remove_file(&path).map_err(|_| Error::CannotDeleteFile)?;
+	pub fn read_from_file(storage: &dyn Storage, path: &Path) -> Result<(Self, bool), Error> { // Object and if it is a version 0 file.
+		// Read from file
+		let data = storage.read(path)?;
+		Self::read_from_data(data)
+	}
+
+	/// Load a file reader from file data that has already been read.
+	pub fn read_from_data(data: Vec<u8>) -> Result<(Self, bool), Error> { // Object and if it is a version 0 file.
+		// Verify the checksum for files that have one, files with a checksum were saved after version 0
+		if data.get(0..4) == Some(CHECKSUM_MAGIC.as_slice()) {
+			let checksum: [u8; 8] = data.get(4..12).ok_or(Error::OutOfBoundsFileRead)?.try_into().expect("[u8] of length 8 should be castable to [u8; 8].");
+			let checksum = u64::from_le_bytes(checksum);
+			let body = data.get(12..).ok_or(Error::OutOfBoundsFileRead)?;
+			if crc64(0, body) != checksum {
+				return Err(Error::ChecksumMismatch);
 			}
-			rename(&backup_path, &path).map_err(|_| Error::CannotRenameFile)?;
+			return Ok((Self::from_body(body.to_vec()), false));
+		}
+		// Files saved with a checksum that have a damaged magic value should not be read as files saved before checksums were added
+		if has_damaged_checksum_magic(&data) {
+			return Err(Error::ChecksumMismatch);
+		}
+		// Files without a checksum should at least have the value that tells if they are version 0
+		if data.len() < 4 {
+			return Err(Error::OutOfBoundsFileRead);
 		}
-		// Read from file
-		let data = read(&path).map_err(|_| Error::CannotReadFile)?;
 		// Special for files encoded in file version 0
-		let val_0: [u8; 4] = data.get(0..4).ok_or(Error::OutOfBoundsFileRead)?.try_into().expect("[u8] of length 4 should be castable to [u8; 4].");
+		let val_0: [u8; 4] = data[0..4].try_into().expect("[u8] of length 4 should be castable to [u8; 4].");
 		let val_0 = u32::from_le_bytes(val_0);
 		let (strings, is_version_0, body_start_ptr) = if val_0 == 0 {
 			let string_area_ptr: [u8; 4] = data.get(4..8).ok_or(Error::OutOfBoundsFileRead)?.try_into().expect("[u8] of length 4 should be castable to [u8; 4].");
@@ -45,9 +65,43 @@ impl FileReader {
 			data,
 			strings_v0: strings,
 			read_index: 0,
+			nesting_depth: 0,
 		}, is_version_0))
 	}
 
+	/// Create a file reader for data that is part of a file, such as a field of a record.
+	pub fn from_body(data: Vec<u8>) -> Self {
+		Self {
+			data,
+			strings_v0: Vec::new(),
+			read_index: 0,
+			nesting_depth: 0,
+		}
+	}
+
+	/// Get where data of a length that starts at the read index ends, erroring if it goes past the end of the file.
+	pub fn get_data_end(&self, length: usize) -> Result<usize, Error> {
+		let end = self.read_index + length;
+		match end > self.data.len() {
+			true => Err(Error::InvalidDataLength),
+			false => Ok(end),
+		}
+	}
+
+	/// Call before reading a tile or item that can have tiles or items inside it.
+	pub fn enter_nested(&mut self) -> Result<(), Error> {
+		if self.nesting_depth >= MAX_NESTING_DEPTH {
+			return Err(Error::TooDeeplyNested);
+		}
+		self.nesting_depth += 1;
+		Ok(())
+	}
+
+	/// Call after reading a tile or item that can have tiles or items inside it.
+	pub fn exit_nested(&mut self) {
+		self.nesting_depth = self.nesting_depth.saturating_sub(1);
+	}
+
 	/// Get a string at a index in the string area.
 	pub fn get_string_v0(&self, start_index: u32) -> Option<String> {
 		// Get slice starting at index
@@ -77,6 +131,31 @@ impl FileReader {
 		Ok(out)
 	}
 
+	/// Read a variable length integer pushed by push_var_u32.
+	pub fn read_var_u32(&mut self) -> Result<u32, Error> {
+		let mut out = 0u32;
+		for shift in [0, 7, 14, 21, 28] {
+			let byte = self.read_u8()?;
+			// The fifth byte only holds the top 4 bits and should be the last byte
+			if shift == 28 && byte > 0x0F {
+				return Err(Error::InvalidVarInt);
+			}
+			out |= ((byte & 0x7F) as u32) << shift;
+			if byte & 0x80 == 0 {
+				break;
+			}
+		}
+		Ok(out)
+	}
+
+	/// Read a namespaced ID, IDs saved before version 10 are a single byte.
+	pub fn read_id(&mut self, version: u32) -> Result<usize, Error> {
+		Ok(match version {
+			0..=9 => self.read_u8()? as usize,
+			_ => self.read_var_u32()? as usize,
+		})
+	}
+
 	pub fn read_u16(&mut self) -> Result<u16, Error> {
 		// Get the 2 bytes that make up the u16 value, they should be little endian.
 		let u16_bytes = self.data.get(self.read_index..self.read_index + 2).ok_or(Error::OutOfBoundsFileRead)?.try_into().expect("[u8] of length 2 should be castable to [u8; 2].");
@@ -121,6 +200,13 @@ impl FileReader {
 		Ok(out)
 	}
 
+	pub fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, Error> {
+		let end = self.read_index.checked_add(length).ok_or(Error::OutOfBoundsFileRead)?;
+		let out = self.data.get(self.read_index..end).ok_or(Error::OutOfBoundsFileRead)?.to_vec();
+		self.read_index = end;
+		Ok(out)
+	}
+
 	pub fn read_world_pos(&mut self) -> Result<[i64; 2], Error> {
 		let x = self.read_i64()?;
 		let y = self.read_i64()?;
@@ -129,7 +215,7 @@ impl FileReader {
 
 	pub fn read_string(&mut self) -> Result<String, Error> {
 		// Get slice starting at the read index
-		let string_start_onwards = self.data.get(self.read_index..).expect("read_index shold not be greater than the data length.");
+		let string_start_onwards = self.data.get(self.read_index..).ok_or(Error::OutOfBoundsFileRead)?;
 		// Find null char and get the string length from it's position.
 		let string_length = string_start_onwards.iter().position(|item| *item == 0).ok_or(Error::UnterminatedStringRead)?;
 		// Get string from start untill null char
@@ -140,4 +226,45 @@ impl FileReader {
 
 		Ok(out)
 	}
+}
+
+/// Weather data looks like it was saved with a checksum but the magic value at the start is damaged.
+/// This is the case if all but one byte of the magic value match or if the checksum after it matches the rest of the data, either is very unlikely for files saved before checksums were added.
+fn has_damaged_checksum_magic(data: &[u8]) -> bool {
+	let magic_matches = match data.get(0..4) {
+		Some(magic) => magic.iter().zip(CHECKSUM_MAGIC).filter(|(byte, magic_byte)| **byte == *magic_byte).count() >= 3,
+		None => false,
+	};
+	let checksum_matches = match (data.get(4..12), data.get(12..)) {
+		(Some(checksum), Some(body)) => u64::from_le_bytes(checksum.try_into().expect("[u8] of length 8 should be castable to [u8; 8].")) == crc64(0, body),
+		_ => false,
+	};
+	magic_matches || checksum_matches
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::error::Error;
+
+	use super::FileReader;
+
+	#[test]
+	fn var_u32_with_a_fifth_byte_above_0x0f_is_rejected() {
+		let mut reader = FileReader::from_body(vec![0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+		assert_eq!(reader.read_var_u32().unwrap(), u32::MAX);
+		for fifth_byte in [0x10, 0x80, 0xFF] {
+			let mut reader = FileReader::from_body(vec![0x80, 0x80, 0x80, 0x80, fifth_byte]);
+			assert!(matches!(reader.read_var_u32(), Err(Error::InvalidVarInt)));
+		}
+	}
+
+	#[test]
+	fn ids_are_bytes_before_version_10() {
+		let mut reader = FileReader::from_body(vec![0x80, 0x01]);
+		assert_eq!(reader.read_id(9).unwrap(), 0x80);
+		assert_eq!(reader.read_index, 1);
+		let mut reader = FileReader::from_body(vec![0x80, 0x01]);
+		assert_eq!(reader.read_id(10).unwrap(), 128);
+		assert_eq!(reader.read_index, 2);
+	}
 }
\ No newline at end of file
@@ -1,4 +1,13 @@
-use std::{path::PathBuf, fs::{File, remove_file}, io::Write};
+use std::path::Path;
+
+use crc64::crc64;
+
+use crate::error::Error;
+
+use super::{serializable::Serializable, storage::Storage};
+
+/// Put at the start of files that have a checksum so they can be told apart from files saved before checksums were added.
+pub const CHECKSUM_MAGIC: [u8; 4] = *b"CLMD";
 
 pub struct FileWriter {
 	pub data: Vec<u8>, // The main vec for storing data
@@ -12,32 +21,18 @@ impl FileWriter {
 		}
 	}
 
-	/// Write the data in the file writer to a new file at path, overwriting it if it exists.
-	pub fn write(&self, path: &PathBuf) -> Option<()> {
-		// Move old file
-		let mut backup_path = path.clone();
-		backup_path.push(".bak");
-		if backup_path.exists() {
-			remove_file(&backup_path).ok()?;
-		}
-		if path.exists() {
-			remove_file(&path).ok()?;
-			//rename(&path, &backup_path).ok()?;
-		}
-		// Create file
-		let mut file = File::create(path).ok()?;
-		// Write body
-		file.write(self.data.as_slice()).ok()?;
-		// Delete old file
-		if backup_path.exists() {
-			remove_file(backup_path).ok()?;
-		}
-		Some(())
+	/// Write the data in the file writer to a file at path in a storage, overwriting it if it exists.
+	pub fn write(&self, storage: &dyn Storage, path: &Path) -> Result<(), Error> {
+		storage.write(path, self.write_to_vec().ok_or(Error::CannotWriteFile)?.as_slice())
 	}
 
 	/// Write the data in the file writer to a u8 vector, allowing it to be written to a file later.
+	/// The data is prefixed with a checksum of the data.
 	pub fn write_to_vec(&self) -> Option<Vec<u8>> {
 		let mut out = Vec::new();
+		// Write checksum
+		out.extend(CHECKSUM_MAGIC);
+		out.extend(crc64(0, self.data.as_slice()).to_le_bytes());
 		// Write body
 		out.extend(self.data.as_slice());
 
@@ -78,8 +73,60 @@ impl FileWriter {
 		self.data.extend(to_push.to_le_bytes());
 	}
 
+	/// Push a variable length integer, each byte holds 7 bits starting from the lowest bits and has its top bit set if more bytes follow.
+	pub fn push_var_u32(&mut self, mut to_push: u32) {
+		while to_push >= 0x80 {
+			self.push_u8(to_push as u8 | 0x80);
+			to_push >>= 7;
+		}
+		self.push_u8(to_push as u8);
+	}
+
+	/// Push a namespaced ID, IDs are variable length so that a category can have any amount of names.
+	pub fn push_id(&mut self, id: usize) {
+		self.push_var_u32(id as u32);
+	}
+
 	pub fn push_world_pos(&mut self, to_push: [i64; 2]) {
 		self.push_i64(to_push[0]);
 		self.push_i64(to_push[1]);
 	}
+
+	/// Push a field of a record, the data of the field is pushed by the closure.
+	pub fn push_field(&mut self, name: &str, push: impl FnOnce(&mut FileWriter)) {
+		let mut field = FileWriter::new();
+		push(&mut field);
+		self.push_str(name);
+		self.push_u32(field.data.len() as u32);
+		self.data.extend(field.data);
+	}
+
+	/// Push a field of a record that holds a serializable value.
+	pub fn push_value_field(&mut self, name: &str, value: &impl Serializable) {
+		self.push_field(name, |field| value.serialize(field));
+	}
+
+	/// Push the end of a record.
+	pub fn push_record_end(&mut self) {
+		self.push_u8(0);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::io::file_reader::FileReader;
+
+	use super::FileWriter;
+
+	#[test]
+	fn var_u32_round_trips() {
+		for (value, length) in [(0, 1), (127, 1), (128, 2), (16383, 2), (16384, 3), (u32::MAX, 5)] {
+			let mut file = FileWriter::new();
+			file.push_var_u32(value);
+			assert_eq!(file.data.len(), length, "{value} should be pushed as {length} bytes.");
+			let mut reader = FileReader::from_body(file.data);
+			assert_eq!(reader.read_var_u32().unwrap(), value);
+			assert_eq!(reader.read_index, length);
+		}
+	}
 }
\ No newline at end of file
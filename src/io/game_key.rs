@@ -24,6 +24,7 @@ pub enum GameKey {
 	OpenSpawnItemsMenu,
 	DeleteItem,
 	OpenCraftingMenu,
+	Sprint,
 }
 
 impl GameKey {
@@ -52,6 +53,7 @@ impl GameKey {
 			VirtualKeyCode::Equals => vec![Self::OpenSpawnItemsMenu],
 			VirtualKeyCode::Minus => vec![Self::DeleteItem],
 			VirtualKeyCode::C => vec![Self::OpenCraftingMenu],
+			VirtualKeyCode::Space => vec![Self::Sprint],
 			_ => Vec::new(),
 		}
 	}
@@ -1,21 +1,15 @@
 use crate::gui::gui_alignment::GUIAlignment;
-use crate::world::difficulty::Difficulty;
-use crate::world::entity::entity_action_state::EntityActionStateVariant;
-use crate::world::{entity::entity_type::EntityVariant, direction::Direction4};
-use crate::world::item::item::ItemVariant;
-use std::{path::PathBuf, fs::create_dir};
-use crate::world::tile::tile::TileVariant;
+use std::{path::{Path, PathBuf}, sync::Arc};
 
 use crc64::crc64;
 use glium::glutin::{event::{KeyboardInput, ElementState, MouseButton}, dpi::PhysicalSize};
-use home::home_dir;
-use strum::{EnumCount, IntoEnumIterator};
+use strum::EnumCount;
 use tokio::runtime::Runtime;
 
-use super::{game_key::GameKey, file_writer::FileWriter};
+use super::{game_key::GameKey, file_writer::FileWriter, namespace::Namespace, settings::Settings, storage::{Storage, FolderStorage, MemoryStorage}, data_paths::DataPaths};
 
 // The version that namespace files will contain and can be used to decide how an old file should be loaded.
-pub const SERIALIZATION_VERSION: u32 = 2;
+pub const SERIALIZATION_VERSION: u32 = 10;
 
 /// For everything hardware related.
 pub struct IO {
@@ -26,71 +20,55 @@ pub struct IO {
 	pub window_size: [u32; 2],
 	pub mouse_pos: [u32; 2],
 	pub key_chars: Vec<char>,
-	pub home_path: PathBuf,
-	pub worlds_path: PathBuf,
+	pub storage: Arc<dyn Storage>, // Where worlds, backups and settings are kept.
+	pub data_path: PathBuf,
+	pub worlds_paths: Vec<PathBuf>, // Folders that worlds are kept in, new worlds are created in the first folder.
+	pub backups_path: PathBuf,
+	pub exports_path: PathBuf,
 	pub char_widths: Vec<u8>,
 	pub async_runtime: Runtime,
 	pub namespace: FileWriter,
 	pub namespace_hash: u64,
 	pub mouse_scroll_delta: i16,
+	pub settings: Settings,
 }
 
 impl IO {
-	pub fn new() -> Self {
+	/// Create the IO for the game, keeping data on disk in the folders of the data paths.
+	pub fn new(data_paths: DataPaths) -> Self {
+		Self::with_storage(Arc::new(FolderStorage), data_paths)
+	}
+
+	/// Create an IO that keeps worlds in memory, nothing is saved to disk.
+	pub fn new_in_memory() -> Self {
+		Self::with_storage(Arc::new(MemoryStorage::new()), DataPaths::from_data_path(PathBuf::from("ceolmund")))
+	}
+
+	/// Create an IO that keeps worlds, backups and settings in the folders of the data paths in a storage.
+	pub fn with_storage(storage: Arc<dyn Storage>, data_paths: DataPaths) -> Self {
 		// Get and create paths
-		let mut home_path = home_dir().unwrap();
-		home_path.push(".ceolmund");
-		let mut worlds_path = home_path.clone();
-		worlds_path.push("worlds");
-		create_dir(&home_path).ok();
-		create_dir(&worlds_path).ok();
+		let DataPaths { data_path, worlds_paths } = data_paths;
+		storage.create_folder(&data_path).ok();
+		for worlds_path in &worlds_paths {
+			storage.create_folder(worlds_path).ok();
+		}
+		let mut backups_path = data_path.clone();
+		backups_path.push("backups");
+		storage.create_folder(&backups_path).ok();
+		let mut exports_path = data_path.clone();
+		exports_path.push("exports");
+		storage.create_folder(&exports_path).ok();
+		// Load settings
+		let mut settings_path = data_path.clone();
+		settings_path.push("settings.txt");
+		let settings = Settings::load(&*storage, &settings_path);
 		// Get the widths of chars in the gui.
 		let mut char_widths = Vec::new();
 		char_widths.extend(include_bytes!("../asset/render_width/0.cwt"));
 		char_widths.extend(include_bytes!("../asset/render_width/1.cwt"));
 		char_widths.extend(include_bytes!("../asset/render_width/2.cwt"));
 		// Create namespace for saving worlds
-		let mut namespace = FileWriter::new();
-		// Add version to namespace
-		namespace.push_u32(SERIALIZATION_VERSION);
-		// Add tile namespace
-		namespace.push_str("tile");
-		for variant in TileVariant::iter() {
-			namespace.push_str(variant.get_name_id());
-		}
-		namespace.push_u8(0);
-		// Add item namespace
-		namespace.push_str("item");
-		for variant in ItemVariant::iter() {
-			namespace.push_str(variant.get_name_id());
-		}
-		namespace.push_u8(0);
-		// Add entity namespace
-		namespace.push_str("entity");
-		for variant in EntityVariant::iter() {
-			namespace.push_str(variant.get_name_id());
-		}
-		namespace.push_u8(0);
-		// Add direction 4 namespace
-		namespace.push_str("direction_4");
-		for variant in Direction4::iter() {
-			namespace.push_str(variant.get_name_id());
-		}
-		namespace.push_u8(0);
-		// Add entity action state namespace
-		namespace.push_str("entity_action_state");
-		for variant in EntityActionStateVariant::iter() {
-			namespace.push_str(variant.get_name_id());
-		}
-		namespace.push_u8(0);
-		// Add difficulties
-		namespace.push_str("difficulty");
-		for variant in Difficulty::iter() {
-			namespace.push_str(variant.get_name_id());
-		}
-		namespace.push_u8(0);
-		// End namespaces
-		namespace.push_u8(0);
+		let namespace = Namespace::current().serialize();
 
 		// Get namespace hash
 		let namespace_hash = crc64(0, namespace.data.as_slice());
@@ -106,16 +84,25 @@ impl IO {
 			window_size: [0, 0],
 			mouse_pos: [0, 0],
 			key_chars: Vec::new(),
-			home_path,
-			worlds_path,
+			storage,
+			data_path,
+			worlds_paths,
+			backups_path,
+			exports_path,
 			char_widths,
 			async_runtime: Runtime::new().unwrap(),
 			namespace,
 			namespace_hash,
 			mouse_scroll_delta: 0,
+			settings,
 		}
 	}
 
+	/// Get the worlds folder that new worlds are created in.
+	pub fn get_new_worlds_path(&self) -> &Path {
+		&self.worlds_paths[0]
+	}
+
 	/// Set a game key as pressed or unpressed.
 	pub fn key_press(&mut self, keyboard_input: &KeyboardInput) {
 		for game_key in GameKey::from_key_code(keyboard_input.virtual_keycode) {
@@ -154,6 +141,7 @@ impl IO {
 			self.game_keys_gamepad[GameKey::MoveWithoutChangingDirection as usize] = gamepad.west_button();
 			self.game_keys_gamepad[GameKey::Turbo as usize] = gamepad.south_button();
 			self.game_keys_gamepad[GameKey::MenuOpenClose as usize] = gamepad.select_button();
+			self.game_keys_gamepad[GameKey::Sprint as usize] = gamepad.left_thumb_button();
 		}
 	}
 
@@ -0,0 +1,180 @@
+use std::path::{Path, PathBuf};
+
+use crc64::crc64;
+use strum_macros::EnumIter;
+
+use crate::{error::Error, world::{chunk::chunk::Chunk, difficulty::Difficulty, direction::Direction4, entity::entity::Entity, item::item::Item, tile::{tile::Tile, tile_stack::GENERATOR_VERSION}, world_overview::WorldOverview}};
+
+use super::{file_writer::{FileWriter, CHECKSUM_MAGIC}, io::SERIALIZATION_VERSION, namespace::Namespace, storage::{MemoryStorage, Storage}};
+
+/// The folder that the namespaces of loaded files are kept in.
+const NAMESPACES_PATH: &str = "namespaces";
+/// Where the file that is loaded is written to.
+const LOADED_FILEPATH: &str = "loaded";
+
+/// A type of save file that can be loaded from untrusted data.
+#[derive(Clone, Copy, EnumIter)]
+pub enum LoadTarget {
+	Chunk,
+	Player,
+	Overview,
+	Namespace,
+}
+
+/// Create a storage holding the current namespace and a namespace for each older serialization version, so that files that refer to any of them can be loaded.
+/// Namespaces of older versions are saved under their version as their hash.
+pub fn create_load_storage() -> MemoryStorage {
+	let storage = MemoryStorage::new();
+	let namespaces_filepath = PathBuf::from(NAMESPACES_PATH);
+	storage.create_folder(&namespaces_filepath).expect("A memory storage should be empty.");
+	let current = Namespace::current();
+	for version in 1..SERIALIZATION_VERSION {
+		let categories = current.get_categories().into_iter().map(|(category, names)| (category.to_string(), names.clone())).collect();
+		let namespace = Namespace::from_categories(version, categories).expect("The current namespace should be valid for older versions.");
+		namespace.serialize().write(&storage, &namespaces_filepath.join(format!("{:0>16x}.nsp", version))).expect("A memory storage should be writable.");
+	}
+	let file = current.serialize();
+	file.write(&storage, &namespaces_filepath.join(format!("{:0>16x}.nsp", get_current_namespace_hash()))).expect("A memory storage should be writable.");
+	storage
+}
+
+/// Get the hash of the current namespace, the same as the hash that worlds are saved with.
+pub fn get_current_namespace_hash() -> u64 {
+	crc64(0, Namespace::current().serialize().data.as_slice())
+}
+
+/// Get valid files of a type saved by the current version of the game, for starting to mutate from.
+pub fn get_seed_files(target: LoadTarget) -> Vec<Vec<u8>> {
+	let namespace_hash = get_current_namespace_hash();
+	let files = match target {
+		LoadTarget::Chunk => vec![get_seed_chunk().serialize_file(namespace_hash)],
+		LoadTarget::Player => vec![Entity::new_player(Difficulty::Medium).serialize_player(namespace_hash)],
+		LoadTarget::Overview => vec![WorldOverview { name: "World".to_string(), seed: 42, difficulty: Difficulty::Hard, time: 1000, generator_version: GENERATOR_VERSION, world_id: Some(7), version: SERIALIZATION_VERSION }.serialize(namespace_hash)],
+		LoadTarget::Namespace => vec![Namespace::current().serialize()],
+	};
+	files.iter().map(|file: &FileWriter| file.write_to_vec().expect("Seed files should be small enough to save.")).collect()
+}
+
+/// Get a chunk with a pattern of tile stacks that use every kind of tile data.
+fn get_seed_chunk() -> Chunk {
+	let tile_patterns = [
+		vec![Tile::Grass],
+		vec![Tile::Water, Tile::Boat],
+		vec![Tile::Sand, Tile::DroppedItemStack(Box::new(Item::Tile(Tile::Item(Box::new(Item::Axe)))), 3)],
+		vec![Tile::Path, Tile::Door(true)],
+		vec![Tile::Gravel, Tile::OneWayPath(Direction4::East), Tile::Gate(false)],
+		vec![Tile::Grass, Tile::Unknown("future_tile".to_string(), vec![1, 2, 3])],
+	];
+	let mut chunk = Chunk::new_blank();
+	for (y, tile_stack_row) in chunk.tile_stacks.iter_mut().enumerate() {
+		for (x, tile_stack) in tile_stack_row.iter_mut().enumerate() {
+			tile_stack.tiles = tile_patterns[(x / 3 + y * y) % tile_patterns.len()].clone();
+		}
+	}
+	chunk
+}
+
+/// Load a file of a type from data that can not be trusted, first as it is and then with its checksum made valid so that the data after the checksum is read.
+/// Errors are expected, loading should never panic or hang.
+pub fn load_untrusted(storage: &dyn Storage, target: LoadTarget, data: &[u8]) {
+	load_file(storage, target, data).ok();
+	if data.len() >= 12 {
+		let mut data = data.to_vec();
+		data[0..4].copy_from_slice(&CHECKSUM_MAGIC);
+		let checksum = crc64(0, &data[12..]);
+		data[4..12].copy_from_slice(&checksum.to_le_bytes());
+		load_file(storage, target, &data).ok();
+	}
+}
+
+/// Write a file to the storage and load it.
+fn load_file(storage: &dyn Storage, target: LoadTarget, data: &[u8]) -> Result<(), Error> {
+	let filepath = Path::new(LOADED_FILEPATH);
+	let namespaces_filepath = Path::new(NAMESPACES_PATH);
+	storage.write(filepath, data)?;
+	match target {
+		LoadTarget::Chunk => Chunk::new_blank().deserialize(data.to_vec(), storage, namespaces_filepath),
+		LoadTarget::Player => Entity::load_player(storage, filepath, namespaces_filepath).map(|_| ()),
+		LoadTarget::Overview => WorldOverview::load(storage, filepath, namespaces_filepath).map(|_| ()),
+		LoadTarget::Namespace => Namespace::load_from_file(storage, filepath).map(|_| ()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::{Rng, SeedableRng, rngs::StdRng};
+	use strum::IntoEnumIterator;
+
+	use super::{LoadTarget, create_load_storage, get_seed_files, load_file, load_untrusted};
+
+	/// How many mutated files of each type are loaded.
+	const MUTATION_CASES: usize = 256;
+
+	/// Change a file in between one and four random ways.
+	fn mutate(rng: &mut StdRng, data: &mut Vec<u8>) {
+		for _ in 0..rng.gen_range(1..=4) {
+			if data.is_empty() {
+				data.push(rng.gen());
+				continue;
+			}
+			let index = rng.gen_range(0..data.len());
+			match rng.gen_range(0..7) {
+				0 => data[index] ^= 1 << rng.gen_range(0..8),
+				1 => data[index] = rng.gen(),
+				2 => data[index] = [0, 1, 0x7F, 0x80, 0xFF][rng.gen_range(0..5)],
+				3 => data.truncate(index),
+				4 => {
+					let end = rng.gen_range(index..=data.len().min(index + 16));
+					data.drain(index..end);
+				}
+				5 => {
+					let length = rng.gen_range(1..=16);
+					let inserted: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+					data.splice(index..index, inserted);
+				}
+				_ => {
+					let end = rng.gen_range(index..=data.len().min(index + 16));
+					let repeated = data[index..end].to_vec();
+					data.splice(end..end, repeated);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn seed_files_load() {
+		let storage = create_load_storage();
+		for target in LoadTarget::iter() {
+			for data in get_seed_files(target) {
+				load_file(&storage, target, &data).unwrap();
+			}
+		}
+	}
+
+	#[test]
+	fn mutated_files_do_not_panic() {
+		let storage = create_load_storage();
+		for (seed, target) in LoadTarget::iter().enumerate() {
+			let mut rng = StdRng::seed_from_u64(seed as u64);
+			let seed_files = get_seed_files(target);
+			for _ in 0..MUTATION_CASES {
+				let mut data = seed_files[rng.gen_range(0..seed_files.len())].clone();
+				mutate(&mut rng, &mut data);
+				load_untrusted(&storage, target, &data);
+			}
+		}
+	}
+
+	#[test]
+	fn random_files_do_not_panic() {
+		let storage = create_load_storage();
+		let mut rng = StdRng::seed_from_u64(100);
+		for target in LoadTarget::iter() {
+			for _ in 0..MUTATION_CASES {
+				let length = rng.gen_range(0..64);
+				let data: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+				load_untrusted(&storage, target, &data);
+			}
+		}
+	}
+}
\ No newline at end of file
@@ -3,4 +3,14 @@ pub mod game_key;
 pub mod file_writer;
 pub mod file_reader;
 pub mod namespace;
-pub mod namespace_name;
+pub mod registry;
+pub mod region_files;
+pub mod settings;
+pub mod world_archive;
+pub mod backups;
+pub mod save_dump;
+pub mod record;
+pub mod serializable;
+pub mod storage;
+pub mod data_paths;
+pub mod load_fuzzing;
\ No newline at end of file
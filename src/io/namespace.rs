@@ -5,30 +5,82 @@ use crate::world::difficulty::Difficulty;
 use crate::{world::entity::entity_action_state::EntityActionStateVariant, io::io::SERIALIZATION_VERSION};
 use crate::world::entity::entity_type::EntityVariant;
 use crate::world::item::item::ItemVariant;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::Path;
 
 use crate::world::{tile::tile::TileVariant, direction::Direction4};
 
-use super::{file_reader::FileReader, namespace_name::NamespaceName};
+use super::{file_reader::FileReader, file_writer::FileWriter, registry::{IDTable, Registered}, storage::Storage};
 
-/// A namespace loaded from disk
-pub struct Namespace {
-	pub version: u32,
-	pub tiles: Vec<TileVariant>,
-	pub items: Vec<ItemVariant>,
-	pub entities: Vec<EntityVariant>,
-	pub direction_4s: Vec<Direction4>,
-	pub entity_action_states: Vec<EntityActionStateVariant>,
-	pub difficulties: Vec<Difficulty>,
+/// Declare the namespace with an ID table for each category, the categories are saved in the order that they are listed.
+macro_rules! namespace {
+	($($field:ident: $variant:ty),* $(,)?) => {
+		/// A namespace loaded from disk
+		/// Names of tiles and items that this version of the game does not know about are loaded as unknown variants and entity names as None.
+		pub struct Namespace {
+			pub version: u32,
+			$(pub $field: IDTable<$variant>,)*
+		}
+
+		impl Namespace {
+			/// The name of each category in namespace files.
+			pub const CATEGORIES: &'static [&'static str] = &[$(<$variant as Registered>::CATEGORY),*];
+
+			/// Get the namespace for the current version of the game.
+			pub fn current() -> Self {
+				Self {
+					version: SERIALIZATION_VERSION,
+					$($field: IDTable::current(),)*
+				}
+			}
+
+			/// Create a namespace from the names in each category, categories that are missing are empty.
+			pub fn from_categories(version: u32, mut categories: HashMap<String, Vec<String>>) -> Result<Self, Error> {
+				let namespace = Self {
+					version,
+					$($field: IDTable::from_names(categories.remove(<$variant as Registered>::CATEGORY).unwrap_or_default())?,)*
+				};
+				if !categories.is_empty() {
+					return Err(Error::InvalidNamespaceName);
+				}
+				Ok(namespace)
+			}
+
+			/// Get the name of each category and the names in the category.
+			pub fn get_categories(&self) -> Vec<(&'static str, &Vec<String>)> {
+				vec![$((<$variant as Registered>::CATEGORY, &self.$field.names)),*]
+			}
+
+			/// Get the names in the namespace that this version of the game does not know about, each prefixed by its category.
+			pub fn get_unknown_names(&self) -> Vec<String> {
+				let mut out = Vec::new();
+				$(out.extend(self.$field.get_unknown_names().into_iter().map(|name| format!("{} {name}", <$variant as Registered>::CATEGORY)));)*
+				out
+			}
+		}
+	};
+}
+
+namespace! {
+	tiles: TileVariant,
+	items: ItemVariant,
+	entities: EntityVariant,
+	direction_4s: Direction4,
+	entity_action_states: EntityActionStateVariant,
+	difficulties: Difficulty,
 }
 
 impl Namespace {
 	/// Load a namespace from a hash and a namespace folder path.
-	pub fn load(hash: u64, namespaces_filepath: PathBuf) -> Result<Self, Error> {
+	pub fn load(storage: &dyn Storage, hash: u64, namespaces_filepath: &Path) -> Result<Self, Error> {
 		// Get the path of the namespace
-		let mut namespace_filepath = namespaces_filepath.clone();
-		namespace_filepath.push(format!("{:0>16x}.nsp", hash));
-		let (mut file, is_version_0) = FileReader::read_from_file(&namespace_filepath)?;
+		let namespace_filepath = namespaces_filepath.join(format!("{:0>16x}.nsp", hash));
+		Self::load_from_file(storage, &namespace_filepath)
+	}
+
+	/// Load a namespace from the path of a namespace file.
+	pub fn load_from_file(storage: &dyn Storage, namespace_filepath: &Path) -> Result<Self, Error> {
+		let (mut file, is_version_0) = FileReader::read_from_file(storage, namespace_filepath)?;
 		// Get version
 		let version = match is_version_0 {
 			true => 0,
@@ -37,19 +89,8 @@ impl Namespace {
 		if version > SERIALIZATION_VERSION {
 			return Err(Error::FutureSerializationVersion);
 		}
-		// Data to extract
-		let tile_name_map = TileVariant::get_name_map();
-		let item_name_map = ItemVariant::get_name_map();
-		let entity_name_map = EntityVariant::get_name_map();
-		let direction_4_name_map = Direction4::get_name_map();
-		let entity_action_state_name_map = EntityActionStateVariant::get_name_map();
-		let difficulty_name_map = Difficulty::get_name_map();
-		let mut tiles = Vec::new();
-		let mut items = Vec::new();
-		let mut entities = Vec::new();
-		let mut direction_4s = Vec::new();
-		let mut entity_action_states = Vec::new();
-		let mut difficulties = Vec::new();
+		// The names in each category
+		let mut categories: HashMap<String, Vec<String>> = HashMap::new();
 		// For each namespace
 		if version == 0 {
 			let mut body_index = file.read_index;
@@ -61,7 +102,7 @@ impl Namespace {
 					break;
 				}
 				let namespace_name = file.get_string_v0(string_ptr).ok_or(Error::InvalidString)?;
-				let namespace_name = NamespaceName::from_name(&namespace_name)?;
+				let names = categories.entry(namespace_name).or_default();
 				// Point to the next string pointer
 				body_index += 4;
 				// For each name
@@ -73,18 +114,9 @@ impl Namespace {
 						body_index += 4;
 						break;
 					}
-					let name = file.get_string_v0(string_ptr).ok_or(Error::InvalidString)?;
+					names.push(file.get_string_v0(string_ptr).ok_or(Error::InvalidString)?);
 					// Point to the next string pointer
 					body_index += 4;
-					// Convert
-					match namespace_name {
-						NamespaceName::Tile => tiles.push(*tile_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-						NamespaceName::Item => items.push(*item_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-						NamespaceName::Entity => entities.push(*entity_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-						NamespaceName::Direction4 => direction_4s.push(*direction_4_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-						NamespaceName::EntityActionStates => entity_action_states.push(*entity_action_state_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-						NamespaceName::Difficulty => difficulties.push(*difficulty_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-					}
 				}
 			}
 		}
@@ -95,7 +127,7 @@ impl Namespace {
 				if namespace_name.graphemes(true).count() == 0 {
 					break;
 				}
-				let namespace_name = NamespaceName::from_name(&namespace_name)?;
+				let names = categories.entry(namespace_name).or_default();
 				// For each name
 				loop {
 					// Get the name and break if we are at the end of the namespace.
@@ -103,27 +135,29 @@ impl Namespace {
 					if name.graphemes(true).count() == 0 {
 						break;
 					}
-					// Convert
-					match namespace_name {
-						NamespaceName::Tile => tiles.push(*tile_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-						NamespaceName::Item => items.push(*item_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-						NamespaceName::Entity => entities.push(*entity_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-						NamespaceName::Direction4 => direction_4s.push(*direction_4_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-						NamespaceName::EntityActionStates => entity_action_states.push(*entity_action_state_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-						NamespaceName::Difficulty => difficulties.push(*difficulty_name_map.get(&name).ok_or(Error::InvalidNamespaceName)?),
-					}
+					names.push(name);
 				}
 			}
 		}
 
-		Ok(Self {
-			version,
-			tiles,
-			entities,
-			direction_4s,
-			items,
-			entity_action_states,
-			difficulties,
-		})
+		Self::from_categories(version, categories)
+	}
+
+	/// Get the contents of a namespace file for the namespace.
+	pub fn serialize(&self) -> FileWriter {
+		let mut file = FileWriter::new();
+		// Push version
+		file.push_u32(self.version);
+		// Add each category followed by its names
+		for (category, names) in self.get_categories() {
+			file.push_str(category);
+			for name in names.iter() {
+				file.push_str(name);
+			}
+			file.push_u8(0);
+		}
+		// End namespaces
+		file.push_u8(0);
+		file
 	}
 }
\ No newline at end of file
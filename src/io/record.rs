@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+
+use super::{file_reader::FileReader, namespace::Namespace, serializable::Serializable};
+
+/// A record of named fields loaded from a file.
+/// Each field is saved as its name, the length of its data then its data, and the record ends with an empty name.
+/// This allows fields to be added without a new serialization version, fields that are missing get a default and fields that are not known are skipped.
+pub struct Record {
+	fields: HashMap<String, Vec<u8>>,
+}
+
+impl Record {
+	/// Load a record.
+	pub fn deserialize(file: &mut FileReader) -> Result<Self, Error> {
+		let mut fields = HashMap::new();
+		loop {
+			// Get the name of the field and break if we are at the end of the record.
+			let name = file.read_string()?;
+			if name.is_empty() {
+				break;
+			}
+			let length = file.read_u32()? as usize;
+			fields.insert(name, file.read_bytes(length)?);
+		}
+		Ok(Self {
+			fields,
+		})
+	}
+
+	/// Read a field if the record has it.
+	pub fn read<T>(&self, name: &str, read: impl FnOnce(&mut FileReader) -> Result<T, Error>) -> Result<Option<T>, Error> {
+		match self.fields.get(name) {
+			Some(data) => Ok(Some(read(&mut FileReader::from_body(data.clone()))?)),
+			None => Ok(None),
+		}
+	}
+
+	/// Read a field that the record should have.
+	pub fn read_required<T>(&self, name: &str, read: impl FnOnce(&mut FileReader) -> Result<T, Error>) -> Result<T, Error> {
+		self.read(name, read)?.ok_or_else(|| Error::MissingRecordField(name.to_string()))
+	}
+
+	/// Read a field that holds a serializable value if the record has it.
+	pub fn read_value<T: Serializable>(&self, name: &str, namespace: &Namespace, version: u32) -> Result<Option<T>, Error> {
+		self.read(name, |field| T::deserialize(field, namespace, version))
+	}
+
+	/// Read a field that holds a serializable value that the record should have.
+	pub fn read_required_value<T: Serializable>(&self, name: &str, namespace: &Namespace, version: u32) -> Result<T, Error> {
+		self.read_value(name, namespace, version)?.ok_or_else(|| Error::MissingRecordField(name.to_string()))
+	}
+}
\ No newline at end of file
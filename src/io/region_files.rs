@@ -0,0 +1,319 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::{Arc, Mutex}, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::error::Error;
+
+use super::storage::Storage;
+
+/// How many chunks wide and tall the area covered by a region file is.
+pub const REGION_SIZE: i64 = 32;
+/// The size of an entry in the offset table, a u64 offset followed by a u32 length and a u32 capacity.
+const ENTRY_SIZE: u64 = 16;
+/// The size of the offset table at the start of each region file.
+const HEADER_SIZE: u64 = (REGION_SIZE * REGION_SIZE) as u64 * ENTRY_SIZE;
+
+/// The region files of a world. Each file holds the chunks in a 32x32 chunk area and starts with an offset table that has an entry for each chunk.
+/// Access to each region file is locked so chunks can be read and written from multiple tasks at once.
+pub struct RegionFiles {
+	pub storage: Arc<dyn Storage>,
+	chunks_filepath: PathBuf,
+	locks: Mutex<HashMap<[i64; 2], Arc<Mutex<()>>>>,
+}
+
+impl RegionFiles {
+	pub fn new(storage: Arc<dyn Storage>, chunks_filepath: PathBuf) -> Self {
+		Self {
+			storage,
+			chunks_filepath,
+			locks: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Get the pos of the region that a chunk is in.
+	pub fn get_region_pos(chunk_pos: [i64; 2]) -> [i64; 2] {
+		[chunk_pos[0].div_euclid(REGION_SIZE), chunk_pos[1].div_euclid(REGION_SIZE)]
+	}
+
+	/// Get the path of the file for a region.
+	pub fn get_region_filepath(&self, region_pos: [i64; 2]) -> PathBuf {
+		let mut region_filepath = self.chunks_filepath.clone();
+		region_filepath.push(format!("{} {}.rgn", region_pos[0], region_pos[1]));
+		region_filepath
+	}
+
+	/// Get where in a region file the offset table entry for a chunk is.
+	fn get_entry_offset(chunk_pos: [i64; 2]) -> u64 {
+		let x = chunk_pos[0].rem_euclid(REGION_SIZE);
+		let y = chunk_pos[1].rem_euclid(REGION_SIZE);
+		(y * REGION_SIZE + x) as u64 * ENTRY_SIZE
+	}
+
+	/// Get the lock for a region file, creating it if it does not exist.
+	fn get_lock(&self, region_pos: [i64; 2]) -> Arc<Mutex<()>> {
+		let mut locks = self.locks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+		locks.entry(region_pos).or_insert_with(|| Arc::new(Mutex::new(()))).clone()
+	}
+
+	/// Read the data of a chunk, getting None if the chunk has not been saved.
+	pub fn read_chunk(&self, chunk_pos: [i64; 2]) -> Result<Option<Vec<u8>>, Error> {
+		let region_pos = Self::get_region_pos(chunk_pos);
+		let lock = self.get_lock(region_pos);
+		let _guard = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+		// Get region file
+		let region_filepath = self.get_region_filepath(region_pos);
+		if !self.storage.exists(&region_filepath) {
+			return Ok(None);
+		}
+		// Read the entry for the chunk
+		let (offset, length, _capacity) = self.read_entry(&region_filepath, Self::get_entry_offset(chunk_pos))?;
+		if offset == 0 {
+			return Ok(None);
+		}
+		// Read the chunk data
+		Ok(Some(self.storage.read_range(&region_filepath, offset, length as usize)?))
+	}
+
+	/// Write the data of a chunk into free space in the region file, the old data of the chunk is kept until the offset table entry points to the new data so a chunk is not lost if the game crashes while saving.
+	/// The data goes in the first gap between the chunks that is big enough, or the end of the file if there is none, so space left by chunks that moved is used again.
+	pub fn write_chunk(&self, chunk_pos: [i64; 2], data: &[u8]) -> Result<(), Error> {
+		let region_pos = Self::get_region_pos(chunk_pos);
+		let lock = self.get_lock(region_pos);
+		let _guard = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+		// Create the region file or fill out it's offset table if it is too short
+		let region_filepath = self.get_region_filepath(region_pos);
+		let file_length = match self.storage.exists(&region_filepath) {
+			true => self.storage.get_length(&region_filepath)?,
+			false => 0,
+		};
+		if file_length < HEADER_SIZE {
+			self.storage.write_range(&region_filepath, file_length, &vec![0; (HEADER_SIZE - file_length) as usize])?;
+		}
+		// Decide where to put the chunk data
+		let header = self.storage.read_range(&region_filepath, 0, HEADER_SIZE as usize)?;
+		let length = data.len() as u32;
+		let offset = find_free_space(&header, length);
+		// Write the chunk data then the entry, the data is synced before the entry points to it
+		self.storage.write_range(&region_filepath, offset, data)?;
+		let mut entry = Vec::with_capacity(ENTRY_SIZE as usize);
+		entry.extend(offset.to_le_bytes());
+		entry.extend(length.to_le_bytes());
+		entry.extend(length.to_le_bytes());
+		self.storage.write_range(&region_filepath, Self::get_entry_offset(chunk_pos), &entry)
+	}
+
+	/// Move the data of a chunk that could not be loaded into the quarantine folder and remove it from the region file so it can be regenerated.
+	/// The incident is recorded in the quarantine log. If the data cannot be read because of an I/O error then the chunk is left in the region file and the error is returned.
+	pub fn quarantine_chunk(&self, chunk_pos: [i64; 2], reason: &str) -> Result<(), Error> {
+		let data = match self.read_chunk(chunk_pos) {
+			Ok(Some(data)) => Some(data),
+			// There is nothing to quarantine if the chunk is not saved
+			Ok(None) => return Ok(()),
+			// The offset table entry points outside of the region file so there is no data to keep
+			Err(Error::OutOfBoundsFileRead) => None,
+			Err(error) => return Err(error),
+		};
+		// Create quarantine folder
+		let mut quarantine_filepath = self.chunks_filepath.clone();
+		quarantine_filepath.push("quarantine");
+		if !self.storage.exists(&quarantine_filepath) {
+			self.storage.create_folder(&quarantine_filepath)?;
+		}
+		let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+		// Copy the chunk data into the quarantine folder, the chunk is only removed from the region file once the copy is written
+		if let Some(data) = &data {
+			let mut chunk_filepath = quarantine_filepath.clone();
+			chunk_filepath.push(format!("{} {} {}.cnk", chunk_pos[0], chunk_pos[1], time));
+			self.storage.write(&chunk_filepath, data)?;
+		}
+		// Record the incident
+		let mut log_filepath = quarantine_filepath;
+		log_filepath.push("quarantine.log");
+		let data_state = match data {
+			Some(_) => "data moved to quarantine",
+			None => "data was outside of the region file",
+		};
+		self.storage.append(&log_filepath, format!("{time}: chunk {} {}: {reason} ({data_state})\n", chunk_pos[0], chunk_pos[1]).as_bytes())?;
+		// Remove the chunk from the region file
+		self.remove_chunk(chunk_pos)
+	}
+
+	/// Remove a chunk from the offset table of it's region file so that it will be generated when next loaded.
+	pub fn remove_chunk(&self, chunk_pos: [i64; 2]) -> Result<(), Error> {
+		let region_pos = Self::get_region_pos(chunk_pos);
+		let lock = self.get_lock(region_pos);
+		let _guard = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+		let region_filepath = self.get_region_filepath(region_pos);
+		if !self.storage.exists(&region_filepath) {
+			return Ok(());
+		}
+		let file_length = self.storage.get_length(&region_filepath)?;
+		if file_length < HEADER_SIZE {
+			self.storage.write_range(&region_filepath, file_length, &vec![0; (HEADER_SIZE - file_length) as usize])?;
+		}
+		self.storage.write_range(&region_filepath, Self::get_entry_offset(chunk_pos), &[0; ENTRY_SIZE as usize])
+	}
+
+	/// Get the pos of every chunk saved in the region files.
+	pub fn list_chunks(&self) -> Result<Vec<[i64; 2]>, Error> {
+		let mut out = Vec::new();
+		for region_filepath in self.storage.list_folder(&self.chunks_filepath)? {
+			if region_filepath.extension().and_then(|extension| extension.to_str()) != Some("rgn") {
+				continue;
+			}
+			let region_pos = match region_filepath.file_stem().and_then(|stem| stem.to_str()).and_then(parse_chunk_pos) {
+				Some(region_pos) => region_pos,
+				None => continue,
+			};
+			let lock = self.get_lock(region_pos);
+			let _guard = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+			let header = self.storage.read_range(&region_filepath, 0, HEADER_SIZE as usize)?;
+			for y in 0..REGION_SIZE {
+				for x in 0..REGION_SIZE {
+					let chunk_pos = [region_pos[0] * REGION_SIZE + x, region_pos[1] * REGION_SIZE + y];
+					let entry_offset = Self::get_entry_offset(chunk_pos) as usize;
+					let (offset, _length, _capacity) = parse_entry(&header[entry_offset..entry_offset + ENTRY_SIZE as usize]);
+					if offset != 0 {
+						out.push(chunk_pos);
+					}
+				}
+			}
+		}
+		Ok(out)
+	}
+
+	/// Move the chunks saved one chunk per file in "x y.cnk" files into region files, getting how many chunks were moved.
+	pub fn migrate_chunk_files(&self) -> Result<usize, Error> {
+		let mut migrated_count = 0;
+		for chunk_filepath in self.storage.list_folder(&self.chunks_filepath)? {
+			if chunk_filepath.extension().and_then(|extension| extension.to_str()) != Some("cnk") {
+				continue;
+			}
+			// Get the chunk pos from the filename
+			let chunk_pos = match chunk_filepath.file_stem().and_then(|stem| stem.to_str()).and_then(parse_chunk_pos) {
+				Some(chunk_pos) => chunk_pos,
+				None => continue,
+			};
+			// Move the chunk
+			let data = self.storage.read(&chunk_filepath)?;
+			self.write_chunk(chunk_pos, &data)?;
+			self.storage.remove_file(&chunk_filepath)?;
+			migrated_count += 1;
+		}
+		Ok(migrated_count)
+	}
+
+	/// Read the offset, length and capacity of an offset table entry in a region file.
+	fn read_entry(&self, region_filepath: &Path, entry_offset: u64) -> Result<(u64, u32, u32), Error> {
+		Ok(parse_entry(&self.storage.read_range(region_filepath, entry_offset, ENTRY_SIZE as usize)?))
+	}
+}
+
+/// Get the offset, length and capacity from the bytes of an offset table entry.
+fn parse_entry(entry: &[u8]) -> (u64, u32, u32) {
+	let offset = u64::from_le_bytes(entry[0..8].try_into().expect("Slice of length 8 should be castable to [u8; 8]."));
+	let length = u32::from_le_bytes(entry[8..12].try_into().expect("Slice of length 4 should be castable to [u8; 4]."));
+	let capacity = u32::from_le_bytes(entry[12..16].try_into().expect("Slice of length 4 should be castable to [u8; 4]."));
+	(offset, length, capacity)
+}
+
+/// Get the offset of the first space in a region file that is not used by any chunk and can fit data of a length, the space after the last chunk is used if there is no gap between chunks.
+fn find_free_space(header: &[u8], length: u32) -> u64 {
+	// Get the parts of the file used by chunks
+	let mut used_parts: Vec<(u64, u64)> = header.chunks_exact(ENTRY_SIZE as usize).map(parse_entry).filter(|(offset, _, _)| *offset != 0)
+		.map(|(offset, length, capacity)| (offset, offset.saturating_add(length.max(capacity) as u64))).collect();
+	used_parts.sort_unstable();
+	// Find the first gap that is big enough
+	let mut free_start = HEADER_SIZE;
+	for (start, end) in used_parts {
+		if start >= free_start && start - free_start >= length as u64 {
+			return free_start;
+		}
+		free_start = free_start.max(end);
+	}
+	free_start
+}
+
+/// Parse a "x y" chunk pos.
+fn parse_chunk_pos(string: &str) -> Option<[i64; 2]> {
+	let (x, y) = string.split_once(' ')?;
+	Some([x.parse().ok()?, y.parse().ok()?])
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::io::storage::tests::check_both_storages;
+
+	use super::{HEADER_SIZE, RegionFiles};
+
+	#[test]
+	fn chunks_are_read_back_as_written() {
+		check_both_storages("region_read_write", |storage, root| {
+			let region_files = RegionFiles::new(storage.clone(), root.to_path_buf());
+			assert_eq!(region_files.read_chunk([0, 0]).unwrap(), None);
+			region_files.write_chunk([0, 0], &[1, 2, 3]).unwrap();
+			region_files.write_chunk([-1, 40], &[4; 100]).unwrap();
+			region_files.write_chunk([31, 31], &[5, 6]).unwrap();
+			assert_eq!(region_files.read_chunk([0, 0]).unwrap(), Some(vec![1, 2, 3]));
+			assert_eq!(region_files.read_chunk([-1, 40]).unwrap(), Some(vec![4; 100]));
+			assert_eq!(region_files.read_chunk([31, 31]).unwrap(), Some(vec![5, 6]));
+			assert_eq!(region_files.read_chunk([1, 0]).unwrap(), None);
+			// Chunks that grow are moved and chunks that shrink are written into free space
+			region_files.write_chunk([0, 0], &[7; 50]).unwrap();
+			assert_eq!(region_files.read_chunk([0, 0]).unwrap(), Some(vec![7; 50]));
+			assert_eq!(region_files.read_chunk([31, 31]).unwrap(), Some(vec![5, 6]));
+			region_files.write_chunk([0, 0], &[8]).unwrap();
+			assert_eq!(region_files.read_chunk([0, 0]).unwrap(), Some(vec![8]));
+			let mut chunks = region_files.list_chunks().unwrap();
+			chunks.sort_unstable();
+			assert_eq!(chunks, [[-1, 40], [0, 0], [31, 31]]);
+			// Removed chunks are not saved
+			region_files.remove_chunk([31, 31]).unwrap();
+			assert_eq!(region_files.read_chunk([31, 31]).unwrap(), None);
+			assert_eq!(region_files.read_chunk([0, 0]).unwrap(), Some(vec![8]));
+		});
+	}
+
+	#[test]
+	fn quarantined_chunks_are_moved_out_of_the_region_file() {
+		check_both_storages("region_quarantine", |storage, root| {
+			let region_files = RegionFiles::new(storage.clone(), root.to_path_buf());
+			region_files.write_chunk([2, 3], &[1, 2, 3]).unwrap();
+			region_files.quarantine_chunk([2, 3], "Test reason.").unwrap();
+			assert_eq!(region_files.read_chunk([2, 3]).unwrap(), None);
+			let quarantine_filepath = root.join("quarantine");
+			let quarantined: Vec<_> = storage.list_folder(&quarantine_filepath).unwrap().into_iter().filter(|path| path.extension().is_some_and(|extension| extension == "cnk")).collect();
+			assert_eq!(quarantined.len(), 1);
+			assert!(quarantined[0].file_name().unwrap().to_str().unwrap().starts_with("2 3 "));
+			assert_eq!(storage.read(&quarantined[0]).unwrap(), [1, 2, 3]);
+			let log = String::from_utf8(storage.read(&quarantine_filepath.join("quarantine.log")).unwrap()).unwrap();
+			assert!(log.contains("chunk 2 3: Test reason. (data moved to quarantine)"));
+		});
+	}
+
+	#[test]
+	fn chunks_outside_of_the_region_file_are_removed_when_quarantined() {
+		check_both_storages("region_quarantine_outside", |storage, root| {
+			let region_files = RegionFiles::new(storage.clone(), root.to_path_buf());
+			region_files.write_chunk([0, 0], &[1, 2, 3]).unwrap();
+			// Point the entry of the chunk past the end of the file
+			let region_filepath = region_files.get_region_filepath([0, 0]);
+			let mut entry = (HEADER_SIZE * 4).to_le_bytes().to_vec();
+			entry.extend(3u32.to_le_bytes());
+			entry.extend(3u32.to_le_bytes());
+			storage.write_range(&region_filepath, 0, &entry).unwrap();
+			assert!(region_files.read_chunk([0, 0]).is_err());
+			region_files.quarantine_chunk([0, 0], "Test reason.").unwrap();
+			assert_eq!(region_files.read_chunk([0, 0]).unwrap(), None);
+			let log = String::from_utf8(storage.read(&root.join("quarantine").join("quarantine.log")).unwrap()).unwrap();
+			assert!(log.contains("(data was outside of the region file)"));
+		});
+	}
+
+	#[test]
+	fn unsaved_chunks_are_not_quarantined() {
+		check_both_storages("region_quarantine_unsaved", |storage, root| {
+			let region_files = RegionFiles::new(storage.clone(), root.to_path_buf());
+			region_files.quarantine_chunk([5, 5], "Test reason.").unwrap();
+			assert!(!storage.exists(&root.join("quarantine")));
+		});
+	}
+}
\ No newline at end of file
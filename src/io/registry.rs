@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use strum::IntoEnumIterator;
+
+use crate::error::Error;
+
+use super::file_reader::FileReader;
+
+/// A type with variants that are saved as IDs.
+/// The names of the variants are saved in namespace files so that later versions of the game can get the variant of each ID.
+pub trait Registered: Copy + IntoEnumIterator + 'static {
+	/// The name of the category of the type in namespace files.
+	const CATEGORY: &'static str;
+
+	/// Get the name of a variant that is saved in namespace files.
+	fn get_name_id(self) -> &'static str;
+
+	/// Get what a name that this version of the game does not know about is loaded as.
+	/// None makes IDs with the name fail to load when they are used, by default the namespace fails to load.
+	fn from_unknown_name(_name: &str) -> Result<Option<Self>, Error> {
+		Err(Error::InvalidNamespaceName)
+	}
+
+	/// Get a map from the name of each variant to the variant.
+	fn get_name_map() -> HashMap<String, Self> {
+		Self::iter().map(|variant| (variant.get_name_id().to_string(), variant)).collect()
+	}
+}
+
+/// The IDs of a category in a namespace, each ID is the index of a name in the category.
+pub struct IDTable<T: Registered> {
+	pub variants: Vec<Option<T>>, // None for names that this version of the game does not know about.
+	pub names: Vec<String>,
+}
+
+impl<T: Registered> IDTable<T> {
+	/// Get the table for the current version of the game.
+	pub fn current() -> Self {
+		Self {
+			variants: T::iter().map(Some).collect(),
+			names: T::iter().map(|variant| variant.get_name_id().to_string()).collect(),
+		}
+	}
+
+	/// Create a table from the names in a category of a namespace file.
+	pub fn from_names(names: Vec<String>) -> Result<Self, Error> {
+		let name_map = T::get_name_map();
+		let variants = names.iter().map(|name| match name_map.get(name) {
+			Some(variant) => Ok(Some(*variant)),
+			None => T::from_unknown_name(name),
+		}).collect::<Result<Vec<_>, Error>>()?;
+		Ok(Self {
+			variants,
+			names,
+		})
+	}
+
+	/// Get the variant with an ID.
+	pub fn get(&self, id: usize) -> Result<T, Error> {
+		self.variants.get(id).ok_or(Error::IDOutOfNamespaceBounds)?.ok_or_else(|| Error::UnknownName(T::CATEGORY, self.names[id].clone()))
+	}
+
+	/// Get the name with an ID.
+	pub fn get_name(&self, id: usize) -> Result<&String, Error> {
+		self.names.get(id).ok_or(Error::IDOutOfNamespaceBounds)
+	}
+
+	/// Read an ID from a file saved with a serialization version and get its variant.
+	pub fn read(&self, file: &mut FileReader, version: u32) -> Result<T, Error> {
+		self.get(file.read_id(version)?)
+	}
+
+	/// Get the names in the table that this version of the game does not know about.
+	pub fn get_unknown_names(&self) -> Vec<&String> {
+		let name_map = T::get_name_map();
+		self.names.iter().filter(|name| !name_map.contains_key(*name)).collect()
+	}
+}
\ No newline at end of file
@@ -0,0 +1,566 @@
+use std::{collections::{HashMap, HashSet}, iter::Peekable, path::{Path, PathBuf}, str::{Chars, FromStr}};
+
+use crate::{error::Error, world::{chunk::chunk::Chunk, difficulty::Difficulty, direction::Direction4, entity::{entity::Entity, entity_action_state::{EntityActionState, EntityActionStateVariant}, entity_type::{EntityType, EntityVariant}}, item::{inventory::Inventory, item::{Item, ItemVariant}}, tile::tile::{Tile, TileVariant}, world_overview::WorldOverview}};
+
+use super::{io::{IO, SERIALIZATION_VERSION}, namespace::Namespace, region_files::RegionFiles, registry::Registered, storage::Storage};
+
+/// Put at the start of each dump.
+const DUMP_HEADER: &str = "# Ceolmund save dump, edit it then encode it back into a save file with \"ceolmund undump\".\n";
+
+/// A value in a dump, a name that can be followed by a list of argument values in brackets such as "dropped_item_stack(tile(grass), 5)".
+struct DumpValue {
+	name: String,
+	args: Vec<DumpValue>,
+}
+
+/// A "key = value" line in a dump.
+struct DumpField {
+	line: usize,
+	key: String,
+	value: String,
+}
+
+/// Get a readable dump of a save file, the type of the file is decided by its extension.
+/// The file should be in a world folder so that the namespaces the file was saved with can be found.
+pub fn dump_file(filepath: &Path, io: &IO) -> Result<String, Error> {
+	let storage = &*io.storage;
+	match filepath.extension().and_then(|extension| extension.to_str()) {
+		Some("wld") => {
+			let overview = WorldOverview::load(storage, filepath, &find_namespaces_filepath(storage, filepath)?)?;
+			Ok(dump_overview(&overview))
+		}
+		Some("ent") => {
+			let player = Entity::load_player(storage, filepath, &find_namespaces_filepath(storage, filepath)?)?;
+			Ok(dump_player(&player))
+		}
+		Some("cnk") => {
+			let data = storage.read(filepath)?;
+			let mut chunk = Chunk::new_blank();
+			chunk.deserialize(data, storage, &find_namespaces_filepath(storage, filepath)?)?;
+			Ok(dump_chunk(&chunk))
+		}
+		Some("nsp") => Ok(dump_namespace(&Namespace::load_from_file(storage, filepath)?)),
+		_ => Err(Error::UnknownSaveFileType),
+	}
+}
+
+/// Get a readable dump of a chunk saved in the region files of a world.
+pub fn dump_world_chunk(world_filepath: &Path, chunk_pos: [i64; 2], io: &IO) -> Result<String, Error> {
+	let region_files = RegionFiles::new(io.storage.clone(), world_filepath.join("chunks"));
+	let mut chunk = Chunk::new_blank();
+	match chunk.load(chunk_pos, &region_files, world_filepath.join("namespaces"))? {
+		true => Ok(dump_chunk(&chunk)),
+		false => Err(Error::ChunkNotSaved),
+	}
+}
+
+/// Encode a dump back into a save file, saving it with the namespace of the current version of the game.
+/// Files other than namespace files should be in a world folder so that the current namespace can be saved alongside them.
+pub fn undump_file(dump: &str, filepath: &Path, io: &IO) -> Result<(), Error> {
+	let mut fields = parse_fields(dump)?;
+	let kind = take_field(&mut fields, "kind")?;
+	let file = match kind.as_str() {
+		"overview" => parse_overview(&mut fields)?.serialize(io.namespace_hash),
+		"player" => parse_player(&mut fields)?.serialize_player(io.namespace_hash),
+		"chunk" => parse_chunk(&mut fields)?.serialize_file(io.namespace_hash),
+		"namespace" => parse_namespace(&mut fields)?.serialize(),
+		_ => return Err(Error::InvalidDump(format!("Unknown kind of dump \"{kind}\"."))),
+	};
+	check_no_fields_left(&fields)?;
+	if kind != "namespace" {
+		save_current_namespace(&find_namespaces_filepath(&*io.storage, filepath)?, io)?;
+	}
+	file.write(&*io.storage, filepath)
+}
+
+/// Encode a chunk dump back into a chunk saved in the region files of a world.
+pub fn undump_world_chunk(dump: &str, world_filepath: &Path, chunk_pos: [i64; 2], io: &IO) -> Result<(), Error> {
+	let mut fields = parse_fields(dump)?;
+	let kind = take_field(&mut fields, "kind")?;
+	if kind != "chunk" {
+		return Err(Error::InvalidDump(format!("Expected a chunk dump but got a {kind} dump.")));
+	}
+	let chunk = parse_chunk(&mut fields)?;
+	check_no_fields_left(&fields)?;
+	save_current_namespace(&world_filepath.join("namespaces"), io)?;
+	let region_files = RegionFiles::new(io.storage.clone(), world_filepath.join("chunks"));
+	region_files.write_chunk(chunk_pos, &chunk.serialize(io.namespace_hash).ok_or(Error::CannotWriteFile)?)
+}
+
+/// Find the namespaces folder of the world that a save file is in.
+fn find_namespaces_filepath(storage: &dyn Storage, filepath: &Path) -> Result<PathBuf, Error> {
+	filepath.ancestors().skip(1).map(|ancestor| ancestor.join("namespaces")).find(|namespaces_filepath| storage.is_folder(namespaces_filepath)).ok_or(Error::CannotReadNamespace)
+}
+
+/// Save the namespace of the current version of the game to a namespaces folder if it is not there already.
+fn save_current_namespace(namespaces_filepath: &Path, io: &IO) -> Result<(), Error> {
+	let namespace_filepath = namespaces_filepath.join(format!("{:0>16x}.nsp", io.namespace_hash));
+	if io.storage.exists(&namespace_filepath) {
+		return Ok(());
+	}
+	io.namespace.write(&*io.storage, &namespace_filepath).map_err(|_| Error::CannotSaveNamespace)
+}
+
+// Dumping
+
+fn dump_overview(overview: &WorldOverview) -> String {
+	let mut out = DUMP_HEADER.to_string();
+	out.push_str("kind = overview\n");
+	out.push_str(&format!("# Saved with serialization version {}.\n", overview.version));
+	out.push_str(&format!("name = {}\n", overview.name));
+	out.push_str(&format!("seed = {}\n", overview.seed));
+	out.push_str(&format!("difficulty = {}\n", overview.difficulty.get_name_id()));
+	out.push_str(&format!("time = {}\n", overview.time));
+	out.push_str(&format!("generator_version = {}\n", overview.generator_version));
+	if let Some(world_id) = overview.world_id {
+		out.push_str(&format!("world_id = {world_id}\n"));
+	}
+	out
+}
+
+fn dump_player(player: &Entity) -> String {
+	let mut out = DUMP_HEADER.to_string();
+	out.push_str("kind = player\n");
+	out.push_str(&format!("pos = {} {}\n", player.pos[0], player.pos[1]));
+	out.push_str(&format!("facing = {}\n", player.facing.get_name_id()));
+	out.push_str(&format!("action_state = {}\n", dump_entity_action_state(&player.action_state)));
+	out.push_str(&format!("health = {}\n", player.health));
+	out.push_str(&format!("entity = {}\n", EntityVariant::from(&player.entity_type).get_name_id()));
+	match &player.entity_type {
+		EntityType::Player { inventory, selected_item, respawn_pos, is_swaping_item: _, is_boating, is_sprinting: _, stamina } => {
+			out.push_str(&format!("selected_item = {selected_item}\n"));
+			out.push_str(&format!("respawn_pos = {} {}\n", respawn_pos[0], respawn_pos[1]));
+			out.push_str(&format!("is_boating = {is_boating}\n"));
+			out.push_str(&format!("stamina = {stamina}\n"));
+			out.push_str("# Inventory slots that are not listed are empty.\n");
+			for (slot, (item, amount)) in inventory.items.iter().enumerate() {
+				if !item.is_none() {
+					out.push_str(&format!("slot {slot} = {}, {amount}\n", dump_item(item)));
+				}
+			}
+		}
+	}
+	out
+}
+
+fn dump_chunk(chunk: &Chunk) -> String {
+	let mut out = DUMP_HEADER.to_string();
+	out.push_str("kind = chunk\n");
+	out.push_str("# Each line is the x and y of a tile stack in the chunk followed by its tiles from the bottom up.\n");
+	for (y, tile_stack_row) in chunk.tile_stacks.iter().enumerate() {
+		for (x, tile_stack) in tile_stack_row.iter().enumerate() {
+			let tiles: Vec<String> = tile_stack.tiles.iter().map(dump_tile).collect();
+			out.push_str(format!("{x} {y} = {}", tiles.join(", ")).trim_end());
+			out.push('\n');
+		}
+	}
+	out
+}
+
+fn dump_namespace(namespace: &Namespace) -> String {
+	let mut out = DUMP_HEADER.to_string();
+	out.push_str("kind = namespace\n");
+	out.push_str(&format!("version = {}\n", namespace.version));
+	for (category, names) in namespace.get_categories() {
+		out.push_str(&format!("{category} = {}\n", names.join(", ")));
+	}
+	out
+}
+
+fn dump_tile(tile: &Tile) -> String {
+	let name = TileVariant::from(tile).get_name_id();
+	match tile {
+		Tile::DroppedItemStack(item, amount) => format!("{name}({}, {amount})", dump_item(item)),
+		Tile::Item(item) => format!("{name}({})", dump_item(item)),
+		Tile::Door(is_open) | Tile::Gate(is_open) => format!("{name}({is_open})"),
+		Tile::OneWayPath(direction) => format!("{name}({})", direction.get_name_id()),
+		Tile::Unknown(unknown_name, data) => dump_unknown(name, unknown_name, data),
+		_ => name.to_string(),
+	}
+}
+
+fn dump_item(item: &Item) -> String {
+	let name = ItemVariant::from(item).get_name_id();
+	match item {
+		Item::Tile(tile) => format!("{name}({})", dump_tile(tile)),
+		Item::Unknown(unknown_name, data) => dump_unknown(name, unknown_name, data),
+		_ => name.to_string(),
+	}
+}
+
+/// Dump a tile or item that is not known as its name followed by its data in hex if it has data, such as "unknown(chest, 0a01)".
+fn dump_unknown(name: &str, unknown_name: &str, data: &[u8]) -> String {
+	match data.is_empty() {
+		true => format!("{name}({unknown_name})"),
+		false => format!("{name}({unknown_name}, {})", data.iter().map(|byte| format!("{byte:02x}")).collect::<String>()),
+	}
+}
+
+fn dump_entity_action_state(action_state: &EntityActionState) -> String {
+	let name = EntityActionStateVariant::from(action_state).get_name_id();
+	match action_state {
+		EntityActionState::Idle => name.to_string(),
+		EntityActionState::Walking(direction, amount) => format!("{name}({}, {amount})", direction.get_name_id()),
+		EntityActionState::Fishing(ticks_left) => format!("{name}({ticks_left})"),
+	}
+}
+
+// Parsing
+
+/// Get the "key = value" lines of a dump, skipping blank lines and comments that start with "#".
+fn parse_fields(dump: &str) -> Result<Vec<DumpField>, Error> {
+	let mut out = Vec::new();
+	let mut keys = HashSet::new();
+	for (line_index, line) in dump.lines().enumerate() {
+		let line_number = line_index + 1;
+		let trimmed_line = line.trim();
+		if trimmed_line.is_empty() || trimmed_line.starts_with('#') {
+			continue;
+		}
+		let (key, value) = line.split_once('=').ok_or_else(|| Error::InvalidDump(format!("Line {line_number} is not a \"key = value\" line.")))?;
+		let key = key.trim().to_string();
+		if !keys.insert(key.clone()) {
+			return Err(Error::InvalidDump(format!("\"{key}\" on line {line_number} is given more than once.")));
+		}
+		out.push(DumpField {
+			line: line_number,
+			key,
+			value: value.trim().to_string(),
+		});
+	}
+	Ok(out)
+}
+
+/// Remove a field from the fields of a dump and get its value.
+fn take_field(fields: &mut Vec<DumpField>, key: &str) -> Result<String, Error> {
+	let index = fields.iter().position(|field| field.key == key).ok_or_else(|| Error::InvalidDump(format!("\"{key}\" is missing.")))?;
+	Ok(fields.remove(index).value)
+}
+
+/// Remove a field from the fields of a dump and parse its value.
+fn take_parsed_field<T>(fields: &mut Vec<DumpField>, key: &str, parse: fn(&DumpValue) -> Result<T, String>) -> Result<T, Error> {
+	let line = fields.iter().find(|field| field.key == key).map(|field| field.line);
+	let value = take_field(fields, key)?;
+	parse_single_value(&value).and_then(|value| parse(&value)).map_err(|error| Error::InvalidDump(format!("Line {}: {error}", line.unwrap_or(0))))
+}
+
+/// Error if there are any fields that were not used.
+fn check_no_fields_left(fields: &[DumpField]) -> Result<(), Error> {
+	match fields.first() {
+		Some(field) => Err(Error::InvalidDump(format!("Line {}: unknown key \"{}\".", field.line, field.key))),
+		None => Ok(()),
+	}
+}
+
+/// Parse a comma seperated list of values.
+fn parse_values(text: &str) -> Result<Vec<DumpValue>, String> {
+	let mut chars = text.chars().peekable();
+	let values = parse_value_list(&mut chars)?;
+	match chars.next() {
+		Some(chr) => Err(format!("Unexpected \"{chr}\".")),
+		None => Ok(values),
+	}
+}
+
+/// Parse a value that is not part of a list.
+fn parse_single_value(text: &str) -> Result<DumpValue, String> {
+	let mut values = parse_values(text)?;
+	match values.len() {
+		1 => Ok(values.remove(0)),
+		_ => Err(format!("Expected one value but got {}.", values.len())),
+	}
+}
+
+/// Parse a comma seperated list of values, stopping at a closing bracket or the end of the text.
+fn parse_value_list(chars: &mut Peekable<Chars>) -> Result<Vec<DumpValue>, String> {
+	let mut values = Vec::new();
+	loop {
+		// Get name
+		let mut name = String::new();
+		while let Some(chr) = chars.peek() {
+			if matches!(chr, '(' | ')' | ',') {
+				break;
+			}
+			name.push(*chr);
+			chars.next();
+		}
+		let name = name.trim().to_string();
+		// Get arguments
+		let mut args = Vec::new();
+		if chars.peek() == Some(&'(') {
+			chars.next();
+			args = parse_value_list(chars)?;
+			if chars.next() != Some(')') {
+				return Err(format!("Missing \")\" after the arguments of \"{name}\"."));
+			}
+			while chars.peek().is_some_and(|chr| chr.is_whitespace()) {
+				chars.next();
+			}
+		}
+		// An empty list
+		if name.is_empty() {
+			if values.is_empty() && args.is_empty() && chars.peek() != Some(&',') {
+				return Ok(values);
+			}
+			return Err("Expected a value.".to_string());
+		}
+		values.push(DumpValue { name, args });
+		// Go to the next value if there is one
+		if chars.peek() != Some(&',') {
+			return Ok(values);
+		}
+		chars.next();
+	}
+}
+
+/// Get the arguments of a value, erroring if there is not the expected amount.
+fn get_args(value: &DumpValue, count: usize) -> Result<&[DumpValue], String> {
+	match value.args.len() == count {
+		true => Ok(&value.args),
+		false => Err(format!("\"{}\" should have {count} arguments but has {}.", value.name, value.args.len())),
+	}
+}
+
+fn parse_number<T: FromStr>(value: &DumpValue) -> Result<T, String> {
+	get_args(value, 0)?;
+	value.name.parse().map_err(|_| format!("\"{}\" is not a valid number.", value.name))
+}
+
+fn parse_bool(value: &DumpValue) -> Result<bool, String> {
+	get_args(value, 0)?;
+	match value.name.as_str() {
+		"true" => Ok(true),
+		"false" => Ok(false),
+		_ => Err(format!("\"{}\" should be true or false.", value.name)),
+	}
+}
+
+fn parse_name<T: Copy>(value: &DumpValue, name_map: &HashMap<String, T>) -> Result<T, String> {
+	get_args(value, 0)?;
+	name_map.get(&value.name).copied().ok_or_else(|| format!("Unknown name \"{}\".", value.name))
+}
+
+fn parse_direction_4(value: &DumpValue) -> Result<Direction4, String> {
+	parse_name(value, &Direction4::get_name_map())
+}
+
+fn parse_difficulty(value: &DumpValue) -> Result<Difficulty, String> {
+	parse_name(value, &Difficulty::get_name_map())
+}
+
+/// Parse a world pos written as "x y".
+fn parse_world_pos(text: &str) -> Result<[i64; 2], String> {
+	let coordinates: Vec<&str> = text.split_whitespace().collect();
+	match coordinates.as_slice() {
+		[x, y] => Ok([
+			x.parse().map_err(|_| format!("\"{x}\" is not a valid x coordinate."))?,
+			y.parse().map_err(|_| format!("\"{y}\" is not a valid y coordinate."))?,
+		]),
+		_ => Err(format!("\"{text}\" should be an x and y coordinate.")),
+	}
+}
+
+fn parse_tile(value: &DumpValue) -> Result<Tile, String> {
+	let variant = *TileVariant::get_name_map().get(&value.name).ok_or_else(|| format!("Unknown tile \"{}\".", value.name))?;
+	if !matches!(variant, TileVariant::DroppedItemStack | TileVariant::Item | TileVariant::Door | TileVariant::Gate | TileVariant::OneWayPath | TileVariant::Unknown) {
+		get_args(value, 0)?;
+	}
+	Ok(match variant {
+		TileVariant::None => return Err("The none tile cannot be in a tile stack.".to_string()),
+		TileVariant::Grass => Tile::Grass,
+		TileVariant::Water => Tile::Water,
+		TileVariant::Sand => Tile::Sand,
+		TileVariant::PineTree => Tile::PineTree,
+		TileVariant::OakTree => Tile::OakTree,
+		TileVariant::Flowers => Tile::Flowers,
+		TileVariant::FlowersRedYellow => Tile::FlowersRedYellow,
+		TileVariant::Rocks => Tile::Rocks,
+		TileVariant::Gravel => Tile::Gravel,
+		TileVariant::BlackSand => Tile::BlackSand,
+		TileVariant::Path => Tile::Path,
+		TileVariant::DroppedItemStack => {
+			let args = get_args(value, 2)?;
+			Tile::DroppedItemStack(Box::new(parse_item(&args[0])?), parse_number(&args[1])?)
+		}
+		TileVariant::Item => Tile::Item(Box::new(parse_item(&get_args(value, 1)?[0])?)),
+		TileVariant::Boat => Tile::Boat,
+		TileVariant::Door => Tile::Door(parse_bool(&get_args(value, 1)?[0])?),
+		TileVariant::Gate => Tile::Gate(parse_bool(&get_args(value, 1)?[0])?),
+		TileVariant::ShallowWater => Tile::ShallowWater,
+		TileVariant::OneWayPath => Tile::OneWayPath(parse_direction_4(&get_args(value, 1)?[0])?),
+		TileVariant::Unknown => {
+			let (name, data) = parse_unknown(value)?;
+			Tile::Unknown(name, data)
+		}
+	})
+}
+
+fn parse_item(value: &DumpValue) -> Result<Item, String> {
+	let variant = *ItemVariant::get_name_map().get(&value.name).ok_or_else(|| format!("Unknown item \"{}\".", value.name))?;
+	if !matches!(variant, ItemVariant::Tile | ItemVariant::Unknown) {
+		get_args(value, 0)?;
+	}
+	Ok(match variant {
+		ItemVariant::Axe => Item::Axe,
+		ItemVariant::Hammer => Item::Hammer,
+		ItemVariant::None => Item::None,
+		ItemVariant::SandboxDestroyWand => Item::SandboxDestroyWand,
+		ItemVariant::Shovel => Item::Shovel,
+		ItemVariant::Tile => Item::Tile(parse_tile(&get_args(value, 1)?[0])?),
+		ItemVariant::Rock => Item::Rock,
+		ItemVariant::FlintRock => Item::FlintRock,
+		ItemVariant::PineStick => Item::PineStick,
+		ItemVariant::OakStick => Item::OakStick,
+		ItemVariant::SharpendFlint => Item::SharpendFlint,
+		ItemVariant::FlintAxe => Item::FlintAxe,
+		ItemVariant::FlintShovel => Item::FlintShovel,
+		ItemVariant::FlintHammer => Item::FlintHammer,
+		ItemVariant::Acorn => Item::Acorn,
+		ItemVariant::PineCone => Item::PineCone,
+		ItemVariant::FishingRod => Item::FishingRod,
+		ItemVariant::RawTrout => Item::RawTrout,
+		ItemVariant::RawCod => Item::RawCod,
+		ItemVariant::Catfish => Item::Catfish,
+		ItemVariant::Seaweed => Item::Seaweed,
+		ItemVariant::OldBoot => Item::OldBoot,
+		ItemVariant::Boat => Item::Boat,
+		ItemVariant::Unknown => {
+			let (name, data) = parse_unknown(value)?;
+			Item::Unknown(name, data)
+		}
+	})
+}
+
+/// Parse the name and data of a tile or item that is not known.
+fn parse_unknown(value: &DumpValue) -> Result<(String, Vec<u8>), String> {
+	let (name, data) = match value.args.as_slice() {
+		[name] => (name, ""),
+		[name, data] => (name, data.name.as_str()),
+		_ => return Err(format!("\"{}\" should have a name and can have data.", value.name)),
+	};
+	get_args(name, 0)?;
+	let data = match data.len() % 2 == 0 && data.is_ascii() {
+		true => (0..data.len()).step_by(2).map(|index| u8::from_str_radix(&data[index..index + 2], 16).ok()).collect::<Option<Vec<u8>>>(),
+		false => None,
+	};
+	match data {
+		Some(data) => Ok((name.name.clone(), data)),
+		None => Err(format!("The data of \"{}\" should be hex.", name.name)),
+	}
+}
+
+fn parse_entity_action_state(value: &DumpValue) -> Result<EntityActionState, String> {
+	let variant = *EntityActionStateVariant::get_name_map().get(&value.name).ok_or_else(|| format!("Unknown action state \"{}\".", value.name))?;
+	Ok(match variant {
+		EntityActionStateVariant::Idle => {
+			get_args(value, 0)?;
+			EntityActionState::Idle
+		}
+		EntityActionStateVariant::Walking => {
+			let args = get_args(value, 2)?;
+			EntityActionState::Walking(parse_direction_4(&args[0])?, parse_number(&args[1])?)
+		}
+		EntityActionStateVariant::Fishing => EntityActionState::Fishing(parse_number(&get_args(value, 1)?[0])?),
+	})
+}
+
+fn parse_overview(fields: &mut Vec<DumpField>) -> Result<WorldOverview, Error> {
+	Ok(WorldOverview {
+		name: take_field(fields, "name")?,
+		seed: take_parsed_field(fields, "seed", parse_number)?,
+		difficulty: take_parsed_field(fields, "difficulty", parse_difficulty)?,
+		time: take_parsed_field(fields, "time", parse_number)?,
+		// Dumps of overviews from before the generator version was saved do not have it
+		generator_version: match fields.iter().any(|field| field.key == "generator_version") {
+			true => take_parsed_field(fields, "generator_version", parse_number)?,
+			false => 0,
+		},
+		// Dumps of overviews saved without a world ID do not have one, the world is given one when it is loaded
+		world_id: match fields.iter().any(|field| field.key == "world_id") {
+			true => Some(take_parsed_field(fields, "world_id", parse_number)?),
+			false => None,
+		},
+		version: SERIALIZATION_VERSION,
+	})
+}
+
+fn parse_player(fields: &mut Vec<DumpField>) -> Result<Entity, Error> {
+	let pos = take_field(fields, "pos")?;
+	let pos = parse_world_pos(&pos).map_err(Error::InvalidDump)?;
+	let facing = take_parsed_field(fields, "facing", parse_direction_4)?;
+	let action_state = take_parsed_field(fields, "action_state", parse_entity_action_state)?;
+	let health = take_parsed_field(fields, "health", parse_number)?;
+	let entity_variant = take_parsed_field(fields, "entity", |value| parse_name(value, &EntityVariant::get_name_map()))?;
+	let entity_type = match entity_variant {
+		EntityVariant::Player => {
+			let selected_item = take_parsed_field(fields, "selected_item", parse_number)?;
+			let respawn_pos = take_field(fields, "respawn_pos")?;
+			let respawn_pos = parse_world_pos(&respawn_pos).map_err(Error::InvalidDump)?;
+			let is_boating = take_parsed_field(fields, "is_boating", parse_bool)?;
+			let stamina = take_parsed_field(fields, "stamina", parse_number)?;
+			// Get the inventory slots
+			let mut inventory = Inventory::new();
+			let slot_fields: Vec<DumpField>;
+			(slot_fields, *fields) = fields.drain(..).partition(|field| field.key.starts_with("slot "));
+			for field in slot_fields {
+				let line = field.line;
+				let slot: usize = field.key["slot ".len()..].trim().parse().map_err(|_| Error::InvalidDump(format!("Line {line}: \"{}\" is not a valid inventory slot.", field.key)))?;
+				let stack = inventory.items.get_mut(slot).ok_or_else(|| Error::InvalidDump(format!("Line {line}: there is no inventory slot {slot}.")))?;
+				*stack = parse_values(&field.value).and_then(|values| match values.as_slice() {
+					[item, amount] => Ok((parse_item(item)?, parse_number(amount)?)),
+					_ => Err("An inventory slot should be an item followed by an amount.".to_string()),
+				}).map_err(|error| Error::InvalidDump(format!("Line {line}: {error}")))?;
+			}
+			EntityType::Player { inventory, selected_item, respawn_pos, is_swaping_item: false, is_boating, is_sprinting: false, stamina }
+		}
+	};
+	Ok(Entity {
+		pos,
+		facing,
+		action_state,
+		entity_type,
+		health,
+	})
+}
+
+fn parse_chunk(fields: &mut Vec<DumpField>) -> Result<Chunk, Error> {
+	let mut chunk = Chunk::new_blank();
+	let mut is_tile_stack_set = [[false; 64]; 64];
+	let tile_stack_fields: Vec<DumpField>;
+	(tile_stack_fields, *fields) = fields.drain(..).partition(|field| parse_world_pos(&field.key).is_ok());
+	for field in tile_stack_fields {
+		let line = field.line;
+		let pos = parse_world_pos(&field.key).map_err(Error::InvalidDump)?;
+		if !(0..64).contains(&pos[0]) || !(0..64).contains(&pos[1]) {
+			return Err(Error::InvalidDump(format!("Line {line}: {} {} is outside of the chunk.", pos[0], pos[1])));
+		}
+		let tiles = parse_values(&field.value).and_then(|values| values.iter().map(parse_tile).collect::<Result<Vec<Tile>, String>>());
+		chunk.tile_stacks[pos[1] as usize][pos[0] as usize].tiles = tiles.map_err(|error| Error::InvalidDump(format!("Line {line}: {error}")))?;
+		is_tile_stack_set[pos[1] as usize][pos[0] as usize] = true;
+	}
+	// Every tile stack should be in the dump
+	for (y, is_tile_stack_set_row) in is_tile_stack_set.iter().enumerate() {
+		if let Some(x) = is_tile_stack_set_row.iter().position(|is_set| !is_set) {
+			return Err(Error::InvalidDump(format!("Tile stack {x} {y} is missing.")));
+		}
+	}
+	Ok(chunk)
+}
+
+fn parse_namespace(fields: &mut Vec<DumpField>) -> Result<Namespace, Error> {
+	let version = take_parsed_field(fields, "version", parse_number)?;
+	// Names that are not known are kept or rejected the same way as when loading a namespace file
+	let mut categories = HashMap::new();
+	for category in Namespace::CATEGORIES {
+		categories.insert(category.to_string(), take_names_field(fields, category)?);
+	}
+	Namespace::from_categories(version, categories)
+}
+
+/// Remove a field that is a list of names from the fields of a dump and get the names without checking that they are known.
+fn take_names_field(fields: &mut Vec<DumpField>, key: &str) -> Result<Vec<String>, Error> {
+	let line = fields.iter().find(|field| field.key == key).map(|field| field.line);
+	let value = take_field(fields, key)?;
+	parse_values(&value).and_then(|values| values.iter().map(|value| get_args(value, 0).map(|_| value.name.clone())).collect())
+		.map_err(|error| Error::InvalidDump(format!("Line {}: {error}", line.unwrap_or(0))))
+}
\ No newline at end of file
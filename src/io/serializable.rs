@@ -0,0 +1,370 @@
+use crate::error::Error;
+
+use super::{file_reader::FileReader, file_writer::FileWriter, namespace::Namespace};
+
+/// A value that can be saved to a file and loaded back.
+/// Loading takes the namespace and serialization version that the file was saved with so that IDs and old formats can be read.
+pub trait Serializable: Sized {
+	/// Push the value to a file.
+	fn serialize(&self, file: &mut FileWriter);
+
+	/// Load a value from a file.
+	fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<Self, Error>;
+
+	/// Load a value that was added to a file in a serialization version, getting the default for files saved before the value was added.
+	fn deserialize_since(file: &mut FileReader, namespace: &Namespace, version: u32, added_in_version: u32, default: impl FnOnce() -> Self) -> Result<Self, Error> {
+		match version >= added_in_version {
+			true => Self::deserialize(file, namespace, version),
+			false => Ok(default()),
+		}
+	}
+}
+
+impl Serializable for u8 {
+	fn serialize(&self, file: &mut FileWriter) {
+		file.push_u8(*self);
+	}
+
+	fn deserialize(file: &mut FileReader, _namespace: &Namespace, _version: u32) -> Result<Self, Error> {
+		file.read_u8()
+	}
+}
+
+impl Serializable for u16 {
+	fn serialize(&self, file: &mut FileWriter) {
+		file.push_u16(*self);
+	}
+
+	fn deserialize(file: &mut FileReader, _namespace: &Namespace, _version: u32) -> Result<Self, Error> {
+		file.read_u16()
+	}
+}
+
+impl Serializable for u32 {
+	fn serialize(&self, file: &mut FileWriter) {
+		file.push_u32(*self);
+	}
+
+	fn deserialize(file: &mut FileReader, _namespace: &Namespace, _version: u32) -> Result<Self, Error> {
+		file.read_u32()
+	}
+}
+
+impl Serializable for u64 {
+	fn serialize(&self, file: &mut FileWriter) {
+		file.push_u64(*self);
+	}
+
+	fn deserialize(file: &mut FileReader, _namespace: &Namespace, _version: u32) -> Result<Self, Error> {
+		file.read_u64()
+	}
+}
+
+impl Serializable for bool {
+	fn serialize(&self, file: &mut FileWriter) {
+		file.push_u8(*self as u8);
+	}
+
+	fn deserialize(file: &mut FileReader, _namespace: &Namespace, _version: u32) -> Result<Self, Error> {
+		Ok(file.read_u8()? != 0)
+	}
+}
+
+impl Serializable for String {
+	fn serialize(&self, file: &mut FileWriter) {
+		file.push_string(self);
+	}
+
+	fn deserialize(file: &mut FileReader, _namespace: &Namespace, _version: u32) -> Result<Self, Error> {
+		file.read_string()
+	}
+}
+
+/// A world pos.
+impl Serializable for [i64; 2] {
+	fn serialize(&self, file: &mut FileWriter) {
+		file.push_world_pos(*self);
+	}
+
+	fn deserialize(file: &mut FileReader, _namespace: &Namespace, _version: u32) -> Result<Self, Error> {
+		file.read_world_pos()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ceolmund_derive::Serializable;
+	use rand::{Rng, SeedableRng, rngs::StdRng, seq::IteratorRandom};
+	use strum::IntoEnumIterator;
+
+	use crate::{io::{file_reader::FileReader, file_writer::FileWriter, io::SERIALIZATION_VERSION, namespace::Namespace}, world::{difficulty::Difficulty, direction::Direction4,
+		entity::{entity::Entity, entity_action_state::{EntityActionState, EntityActionStateVariant, WALK_PROGRESS_PER_TILE}, entity_type::EntityType}, item::{inventory::Inventory, item::{Item, ItemVariant}},
+		tile::{tile::{Tile, TileVariant}, tile_stack::TileStack}}};
+
+	use super::Serializable;
+
+	/// How many random values of each type are saved and loaded back.
+	const CASES: usize = 256;
+	/// How deeply random tiles and items are nested inside each other.
+	const MAX_TEST_NESTING: u8 = 4;
+
+	/// Save a value to file data and load it back with the current namespace, checking that all of the data is read.
+	fn round_trip<T: Serializable>(value: &T) -> T {
+		let mut file = FileWriter::new();
+		value.serialize(&mut file);
+		let data = file.write_to_vec().expect("Test values should be small enough to save.");
+		let (mut file, is_version_0) = FileReader::read_from_data(data).expect("Saved data should have a valid checksum.");
+		assert!(!is_version_0);
+		let out = T::deserialize(&mut file, &Namespace::current(), SERIALIZATION_VERSION).expect("Saved data should load.");
+		assert_eq!(file.read_index, file.data.len(), "All saved data should be read.");
+		out
+	}
+
+	fn random_string(rng: &mut StdRng) -> String {
+		let length = rng.gen_range(0..16);
+		(0..length).map(|_| match rng.gen_range(0..4) {
+			0 => 'ø',
+			_ => rng.gen_range('!'..='~'),
+		}).collect()
+	}
+
+	fn random_bytes(rng: &mut StdRng) -> Vec<u8> {
+		let length = rng.gen_range(0..32);
+		(0..length).map(|_| rng.gen()).collect()
+	}
+
+	fn random_direction(rng: &mut StdRng) -> Direction4 {
+		Direction4::iter().choose(rng).unwrap()
+	}
+
+	/// Get a random tile, tiles that hold items hold tiles up to a nesting depth.
+	fn random_tile(rng: &mut StdRng, depth: u8) -> Tile {
+		let variant = TileVariant::iter().filter(|variant| *variant != TileVariant::None).choose(rng).unwrap();
+		match variant {
+			TileVariant::None => unreachable!(),
+			TileVariant::Grass => Tile::Grass,
+			TileVariant::Water => Tile::Water,
+			TileVariant::Path => Tile::Path,
+			TileVariant::PineTree => Tile::PineTree,
+			TileVariant::OakTree => Tile::OakTree,
+			TileVariant::Flowers => Tile::Flowers,
+			TileVariant::Rocks => Tile::Rocks,
+			TileVariant::FlowersRedYellow => Tile::FlowersRedYellow,
+			TileVariant::Gravel => Tile::Gravel,
+			TileVariant::BlackSand => Tile::BlackSand,
+			TileVariant::Sand => Tile::Sand,
+			TileVariant::DroppedItemStack => Tile::DroppedItemStack(Box::new(random_item(rng, depth + 1)), rng.gen()),
+			TileVariant::Item => Tile::Item(Box::new(random_item(rng, depth + 1))),
+			TileVariant::Boat => Tile::Boat,
+			TileVariant::Door => Tile::Door(rng.gen()),
+			TileVariant::Gate => Tile::Gate(rng.gen()),
+			TileVariant::OneWayPath => Tile::OneWayPath(random_direction(rng)),
+			TileVariant::ShallowWater => Tile::ShallowWater,
+			TileVariant::Unknown => Tile::Unknown(format!("future_tile_{}", rng.gen::<u16>()), random_bytes(rng)),
+		}
+	}
+
+	/// Get a random item, items that hold tiles are only picked below a nesting depth.
+	fn random_item(rng: &mut StdRng, depth: u8) -> Item {
+		let variant = ItemVariant::iter().filter(|variant| *variant != ItemVariant::Tile || depth < MAX_TEST_NESTING).choose(rng).unwrap();
+		match variant {
+			ItemVariant::None => Item::None,
+			ItemVariant::Hammer => Item::Hammer,
+			ItemVariant::Shovel => Item::Shovel,
+			ItemVariant::Axe => Item::Axe,
+			ItemVariant::SandboxDestroyWand => Item::SandboxDestroyWand,
+			ItemVariant::Tile => Item::Tile(random_tile(rng, depth + 1)),
+			ItemVariant::Rock => Item::Rock,
+			ItemVariant::FlintRock => Item::FlintRock,
+			ItemVariant::PineStick => Item::PineStick,
+			ItemVariant::OakStick => Item::OakStick,
+			ItemVariant::SharpendFlint => Item::SharpendFlint,
+			ItemVariant::FlintHammer => Item::FlintHammer,
+			ItemVariant::FlintShovel => Item::FlintShovel,
+			ItemVariant::FlintAxe => Item::FlintAxe,
+			ItemVariant::Acorn => Item::Acorn,
+			ItemVariant::PineCone => Item::PineCone,
+			ItemVariant::FishingRod => Item::FishingRod,
+			ItemVariant::RawTrout => Item::RawTrout,
+			ItemVariant::RawCod => Item::RawCod,
+			ItemVariant::Catfish => Item::Catfish,
+			ItemVariant::Seaweed => Item::Seaweed,
+			ItemVariant::OldBoot => Item::OldBoot,
+			ItemVariant::Boat => Item::Boat,
+			ItemVariant::Unknown => Item::Unknown(format!("future_item_{}", rng.gen::<u16>()), random_bytes(rng)),
+		}
+	}
+
+	fn random_inventory<const SLOT_COUNT: usize>(rng: &mut StdRng) -> Inventory<SLOT_COUNT> {
+		let mut inventory = Inventory::new();
+		for (stack_item, stack_amount) in inventory.items.iter_mut() {
+			*stack_item = random_item(rng, 0);
+			*stack_amount = rng.gen();
+		}
+		inventory
+	}
+
+	fn random_tile_stack(rng: &mut StdRng) -> TileStack {
+		let mut tile_stack = TileStack::new();
+		let tile_count = rng.gen_range(0..8);
+		tile_stack.tiles = (0..tile_count).map(|_| random_tile(rng, 0)).collect();
+		tile_stack
+	}
+
+	fn random_action_state(rng: &mut StdRng) -> EntityActionState {
+		match EntityActionStateVariant::iter().choose(rng).unwrap() {
+			EntityActionStateVariant::Idle => EntityActionState::Idle,
+			EntityActionStateVariant::Walking => EntityActionState::Walking(random_direction(rng), rng.gen_range(0..=WALK_PROGRESS_PER_TILE)),
+			EntityActionStateVariant::Fishing => EntityActionState::Fishing(rng.gen()),
+		}
+	}
+
+	/// Get a random player, fields of the player that are not saved are left at their defaults.
+	fn random_player(rng: &mut StdRng) -> Entity {
+		Entity {
+			pos: rng.gen(),
+			facing: random_direction(rng),
+			action_state: random_action_state(rng),
+			entity_type: EntityType::Player {
+				inventory: random_inventory(rng),
+				selected_item: rng.gen_range(0..50),
+				respawn_pos: rng.gen(),
+				is_swaping_item: false,
+				is_boating: rng.gen(),
+				is_sprinting: false,
+				stamina: rng.gen(),
+			},
+			health: rng.gen(),
+		}
+	}
+
+	/// Check that every random value of a type loads back as the same value.
+	fn check_round_trips<T: Serializable + PartialEq>(seed: u64, mut random_value: impl FnMut(&mut StdRng) -> T) {
+		let mut rng = StdRng::seed_from_u64(seed);
+		for _ in 0..CASES {
+			let value = random_value(&mut rng);
+			assert!(round_trip(&value) == value);
+		}
+	}
+
+	#[test]
+	fn primitives_round_trip() {
+		check_round_trips(0, |rng| rng.gen::<u8>());
+		check_round_trips(1, |rng| rng.gen::<u16>());
+		check_round_trips(2, |rng| rng.gen::<u32>());
+		check_round_trips(3, |rng| rng.gen::<u64>());
+		check_round_trips(4, |rng| rng.gen::<bool>());
+		check_round_trips(5, random_string);
+		check_round_trips(6, |rng| rng.gen::<[i64; 2]>());
+		for value in [u64::MIN, u64::MAX] {
+			assert_eq!(round_trip(&value), value);
+		}
+		for value in [[i64::MIN, i64::MAX], [0, -1]] {
+			assert_eq!(round_trip(&value), value);
+		}
+	}
+
+	#[test]
+	fn namespaced_enums_round_trip() {
+		for direction in Direction4::iter() {
+			assert_eq!(round_trip(&direction), direction);
+		}
+		for difficulty in Difficulty::iter() {
+			assert!(round_trip(&difficulty) == difficulty);
+		}
+	}
+
+	#[test]
+	fn tiles_round_trip() {
+		check_round_trips(7, |rng| random_tile(rng, 0));
+		// Tiles that hold items that hold tiles
+		let nested = Tile::DroppedItemStack(Box::new(Item::Tile(Tile::Item(Box::new(Item::Tile(Tile::Door(true)))))), 12);
+		assert!(round_trip(&nested) == nested);
+		for tile in [Tile::Door(false), Tile::Gate(true), Tile::OneWayPath(Direction4::West), Tile::Unknown("future_tile".to_string(), vec![0, 1, 2])] {
+			assert!(round_trip(&tile) == tile);
+		}
+	}
+
+	#[test]
+	fn known_tiles_saved_as_unknown_load_as_known() {
+		assert!(round_trip(&Tile::Unknown("door".to_string(), vec![1])) == Tile::Door(true));
+		assert!(round_trip(&Item::Unknown("axe".to_string(), Vec::new())) == Item::Axe);
+	}
+
+	#[test]
+	fn items_round_trip() {
+		check_round_trips(8, |rng| random_item(rng, 0));
+	}
+
+	#[test]
+	fn inventories_round_trip() {
+		let mut rng = StdRng::seed_from_u64(9);
+		for _ in 0..CASES / 16 {
+			let inventory: Inventory<50> = random_inventory(&mut rng);
+			assert!(round_trip(&inventory).items == inventory.items);
+		}
+	}
+
+	#[test]
+	fn tile_stacks_round_trip() {
+		let mut rng = StdRng::seed_from_u64(10);
+		for _ in 0..CASES {
+			let tile_stack = random_tile_stack(&mut rng);
+			assert!(round_trip(&tile_stack).tiles == tile_stack.tiles);
+		}
+	}
+
+	#[test]
+	fn action_states_round_trip() {
+		check_round_trips(11, random_action_state);
+	}
+
+	#[test]
+	fn entities_round_trip() {
+		let mut rng = StdRng::seed_from_u64(12);
+		for _ in 0..CASES / 16 {
+			let entity = random_player(&mut rng);
+			let loaded = round_trip(&entity);
+			assert_eq!(loaded.pos, entity.pos);
+			assert_eq!(loaded.facing, entity.facing);
+			assert!(loaded.action_state == entity.action_state);
+			assert_eq!(loaded.health, entity.health);
+			let (EntityType::Player { inventory, selected_item, respawn_pos, is_boating, stamina, .. }, EntityType::Player { inventory: loaded_inventory, selected_item: loaded_selected_item,
+				respawn_pos: loaded_respawn_pos, is_boating: loaded_is_boating, stamina: loaded_stamina, .. }) = (&entity.entity_type, &loaded.entity_type);
+			assert!(loaded_inventory.items == inventory.items);
+			assert_eq!(loaded_selected_item, selected_item);
+			assert_eq!(loaded_respawn_pos, respawn_pos);
+			assert_eq!(loaded_is_boating, is_boating);
+			assert_eq!(loaded_stamina, stamina);
+		}
+	}
+
+	/// A struct with a field that was added in a later version.
+	#[derive(Serializable, PartialEq, Debug)]
+	struct Versioned {
+		count: u32,
+		#[serializable(since = 5, default = "default".to_string())]
+		name: String,
+	}
+
+	#[test]
+	fn derived_structs_round_trip() {
+		let value = Versioned { count: 7, name: "name".to_string() };
+		assert_eq!(round_trip(&value), value);
+	}
+
+	#[test]
+	fn values_added_later_get_defaults_from_older_versions() {
+		let mut file = FileWriter::new();
+		7u32.serialize(&mut file);
+		let mut file = FileReader::from_body(file.data);
+		let value = Versioned::deserialize(&mut file, &Namespace::current(), 4).unwrap();
+		assert_eq!(value, Versioned { count: 7, name: "default".to_string() });
+		assert_eq!(file.read_index, file.data.len());
+		// Values from the version they were added in are read
+		let mut file = FileReader::from_body(vec![3, 0, 0, 0]);
+		assert_eq!(u32::deserialize_since(&mut file, &Namespace::current(), 2, 2, || 9).unwrap(), 3);
+		let mut file = FileReader::from_body(Vec::new());
+		assert_eq!(u32::deserialize_since(&mut file, &Namespace::current(), 1, 2, || 9).unwrap(), 9);
+	}
+}
\ No newline at end of file
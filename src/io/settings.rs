@@ -0,0 +1,51 @@
+use std::path::Path;
+
+use super::storage::Storage;
+
+/// The default amount of seconds between autosaves.
+const DEFAULT_AUTOSAVE_INTERVAL: u32 = 5 * 60;
+
+/// Game settings that are loaded from and saved to a plain text file of "name = value" lines so they can be edited by hand.
+pub struct Settings {
+	pub autosave_interval: u32, // How many seconds between autosaves, 0 disables autosaving.
+}
+
+impl Settings {
+	/// Load settings from the settings file, using the defaults for settings that are missing or invalid and writing the file if it does not exist.
+	pub fn load(storage: &dyn Storage, settings_filepath: &Path) -> Self {
+		let mut out = Self::default();
+		let text = match storage.read(settings_filepath).ok().and_then(|data| String::from_utf8(data).ok()) {
+			Some(text) => text,
+			None => {
+				out.save(storage, settings_filepath);
+				return out;
+			}
+		};
+		for line in text.lines() {
+			let (name, value) = match line.split_once('=') {
+				Some((name, value)) => (name.trim(), value.trim()),
+				None => continue,
+			};
+			if name == "autosave_interval" {
+				if let Ok(value) = value.parse() {
+					out.autosave_interval = value;
+				}
+			}
+		}
+		out
+	}
+
+	/// Save the settings to the settings file.
+	pub fn save(&self, storage: &dyn Storage, settings_filepath: &Path) -> Option<()> {
+		let text = format!("autosave_interval = {}\n", self.autosave_interval);
+		storage.write(settings_filepath, text.as_bytes()).ok()
+	}
+}
+
+impl Default for Settings {
+	fn default() -> Self {
+		Self {
+			autosave_interval: DEFAULT_AUTOSAVE_INTERVAL,
+		}
+	}
+}
\ No newline at end of file
@@ -0,0 +1,397 @@
+use std::{collections::BTreeMap, fs::{self, File, OpenOptions}, io::{Read, Seek, SeekFrom, Write}, path::{Path, PathBuf}, sync::{Mutex, MutexGuard}};
+
+use crate::error::Error;
+
+/// Where the game keeps the files of worlds, backups and settings. Files and folders are addressed by path and the folder layout is the same for every storage.
+pub trait Storage: Send + Sync {
+	/// Read all of a file.
+	fn read(&self, path: &Path) -> Result<Vec<u8>, Error>;
+	/// Write a file, replacing it if it exists. The old file is kept intact if the write does not finish.
+	fn write(&self, path: &Path, data: &[u8]) -> Result<(), Error>;
+	/// Add data to the end of a file, creating the file if it does not exist.
+	fn append(&self, path: &Path, data: &[u8]) -> Result<(), Error>;
+	/// Read part of a file, erroring if the part goes past the end of the file.
+	fn read_range(&self, path: &Path, offset: u64, length: usize) -> Result<Vec<u8>, Error>;
+	/// Write data at an offset in a file, creating the file and extending it with zeros if needed. The data is synced before returning.
+	fn write_range(&self, path: &Path, offset: u64, data: &[u8]) -> Result<(), Error>;
+	/// Get the length of a file in bytes.
+	fn get_length(&self, path: &Path) -> Result<u64, Error>;
+	/// Weather there is a file or folder at a path.
+	fn exists(&self, path: &Path) -> bool;
+	/// Weather there is a folder at a path.
+	fn is_folder(&self, path: &Path) -> bool;
+	/// Get the paths of the files and folders in a folder.
+	fn list_folder(&self, path: &Path) -> Result<Vec<PathBuf>, Error>;
+	/// Create a folder and any folders it is in that do not exist, erroring if there is already something at the path.
+	fn create_folder(&self, path: &Path) -> Result<(), Error>;
+	fn remove_file(&self, path: &Path) -> Result<(), Error>;
+	/// Remove a folder and everything in it.
+	fn remove_folder(&self, path: &Path) -> Result<(), Error>;
+	/// Move a file or folder, erroring if there is already something at the new path.
+	fn rename(&self, from: &Path, to: &Path) -> Result<(), Error>;
+}
+
+/// Stores files in folders on disk.
+pub struct FolderStorage;
+
+impl Storage for FolderStorage {
+	fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+		fs::read(path).map_err(|_| Error::CannotReadFile)
+	}
+
+	/// The data is written to a temp file that is synced then renamed over the old file so the old file is kept intact if the game crashes while saving.
+	fn write(&self, path: &Path, data: &[u8]) -> Result<(), Error> {
+		let mut temp_path = path.as_os_str().to_os_string();
+		temp_path.push(".tmp");
+		let temp_path = PathBuf::from(temp_path);
+		// Write to temp file
+		let mut file = File::create(&temp_path).map_err(|_| Error::CannotWriteFile)?;
+		file.write_all(data).map_err(|_| Error::CannotWriteFile)?;
+		file.sync_all().map_err(|_| Error::CannotWriteFile)?;
+		drop(file);
+		// Replace old file
+		fs::rename(&temp_path, path).map_err(|_| Error::CannotWriteFile)?;
+		// Sync the folder so the rename is kept, this is not possible on all platforms
+		if let Some(parent_path) = path.parent() {
+			if let Ok(parent) = File::open(parent_path) {
+				parent.sync_all().ok();
+			}
+		}
+		Ok(())
+	}
+
+	fn append(&self, path: &Path, data: &[u8]) -> Result<(), Error> {
+		let mut file = OpenOptions::new().append(true).create(true).open(path).map_err(|_| Error::CannotWriteFile)?;
+		file.write_all(data).map_err(|_| Error::CannotWriteFile)
+	}
+
+	fn read_range(&self, path: &Path, offset: u64, length: usize) -> Result<Vec<u8>, Error> {
+		let mut file = File::open(path).map_err(|_| Error::CannotReadFile)?;
+		let file_length = file.metadata().map_err(|_| Error::CannotReadFile)?.len();
+		// Check that the part is inside the file before making space for it
+		if offset.checked_add(length as u64).is_none_or(|end| end > file_length) {
+			return Err(Error::OutOfBoundsFileRead);
+		}
+		let mut data = vec![0; length];
+		file.seek(SeekFrom::Start(offset)).map_err(|_| Error::CannotReadFile)?;
+		file.read_exact(&mut data).map_err(|_| Error::OutOfBoundsFileRead)?;
+		Ok(data)
+	}
+
+	fn write_range(&self, path: &Path, offset: u64, data: &[u8]) -> Result<(), Error> {
+		let mut file = OpenOptions::new().write(true).create(true).truncate(false).open(path).map_err(|_| Error::CannotWriteFile)?;
+		file.seek(SeekFrom::Start(offset)).map_err(|_| Error::CannotWriteFile)?;
+		file.write_all(data).map_err(|_| Error::CannotWriteFile)?;
+		file.sync_data().map_err(|_| Error::CannotWriteFile)
+	}
+
+	fn get_length(&self, path: &Path) -> Result<u64, Error> {
+		Ok(fs::metadata(path).map_err(|_| Error::CannotReadFile)?.len())
+	}
+
+	fn exists(&self, path: &Path) -> bool {
+		path.exists()
+	}
+
+	fn is_folder(&self, path: &Path) -> bool {
+		path.is_dir()
+	}
+
+	fn list_folder(&self, path: &Path) -> Result<Vec<PathBuf>, Error> {
+		Ok(fs::read_dir(path).map_err(|_| Error::CannotReadFile)?.flatten().map(|item| item.path()).collect())
+	}
+
+	fn create_folder(&self, path: &Path) -> Result<(), Error> {
+		if path.exists() {
+			return Err(Error::CannotCreateFolder);
+		}
+		fs::create_dir_all(path).map_err(|_| Error::CannotCreateFolder)
+	}
+
+	fn remove_file(&self, path: &Path) -> Result<(), Error> {
+		fs::remove_file(path).map_err(|_| Error::CannotDeleteFile)
+	}
+
+	fn remove_folder(&self, path: &Path) -> Result<(), Error> {
+		fs::remove_dir_all(path).map_err(|_| Error::CannotDeleteFile)
+	}
+
+	fn rename(&self, from: &Path, to: &Path) -> Result<(), Error> {
+		if to.exists() {
+			return Err(Error::CannotRenameFile);
+		}
+		fs::rename(from, to).map_err(|_| Error::CannotRenameFile)
+	}
+}
+
+/// A file or folder in a memory storage.
+enum MemoryItem {
+	File(Vec<u8>),
+	Folder,
+}
+
+/// Keeps files in memory so that worlds can be created, played and loaded again without touching the disk. Everything is lost when the storage is dropped.
+pub struct MemoryStorage {
+	items: Mutex<BTreeMap<PathBuf, MemoryItem>>,
+}
+
+impl MemoryStorage {
+	pub fn new() -> Self {
+		Self {
+			items: Mutex::new(BTreeMap::new()),
+		}
+	}
+
+	/// Lock the items, the items are only changed by complete operations so they are still valid if another thread panicked while holding the lock.
+	fn lock(&self) -> MutexGuard<'_, BTreeMap<PathBuf, MemoryItem>> {
+		self.items.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+}
+
+impl Default for MemoryStorage {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Weather the folder that a path is in exists, paths that are not in a folder are at the root of the storage which always exists.
+fn has_parent_folder(items: &BTreeMap<PathBuf, MemoryItem>, path: &Path) -> bool {
+	match path.parent() {
+		Some(parent_path) if parent_path != Path::new("") => matches!(items.get(parent_path), Some(MemoryItem::Folder)),
+		_ => true,
+	}
+}
+
+/// Get a file in a memory storage to write to, creating it if it does not exist.
+fn get_file_mut<'a>(items: &'a mut BTreeMap<PathBuf, MemoryItem>, path: &Path) -> Result<&'a mut Vec<u8>, Error> {
+	if !items.contains_key(path) {
+		if !has_parent_folder(items, path) {
+			return Err(Error::CannotWriteFile);
+		}
+		items.insert(path.to_path_buf(), MemoryItem::File(Vec::new()));
+	}
+	match items.get_mut(path) {
+		Some(MemoryItem::File(data)) => Ok(data),
+		_ => Err(Error::CannotWriteFile),
+	}
+}
+
+/// Get the paths of an item and everything in it if it is a folder.
+fn get_item_paths(items: &BTreeMap<PathBuf, MemoryItem>, path: &Path) -> Vec<PathBuf> {
+	items.keys().filter(|item_path| item_path.starts_with(path)).cloned().collect()
+}
+
+impl Storage for MemoryStorage {
+	fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+		match self.lock().get(path) {
+			Some(MemoryItem::File(data)) => Ok(data.clone()),
+			_ => Err(Error::CannotReadFile),
+		}
+	}
+
+	fn write(&self, path: &Path, data: &[u8]) -> Result<(), Error> {
+		let mut items = self.lock();
+		*get_file_mut(&mut items, path)? = data.to_vec();
+		Ok(())
+	}
+
+	fn append(&self, path: &Path, data: &[u8]) -> Result<(), Error> {
+		let mut items = self.lock();
+		get_file_mut(&mut items, path)?.extend(data);
+		Ok(())
+	}
+
+	fn read_range(&self, path: &Path, offset: u64, length: usize) -> Result<Vec<u8>, Error> {
+		let items = self.lock();
+		let data = match items.get(path) {
+			Some(MemoryItem::File(data)) => data,
+			_ => return Err(Error::CannotReadFile),
+		};
+		let start = usize::try_from(offset).map_err(|_| Error::OutOfBoundsFileRead)?;
+		let end = start.checked_add(length).ok_or(Error::OutOfBoundsFileRead)?;
+		Ok(data.get(start..end).ok_or(Error::OutOfBoundsFileRead)?.to_vec())
+	}
+
+	fn write_range(&self, path: &Path, offset: u64, data: &[u8]) -> Result<(), Error> {
+		let mut items = self.lock();
+		let file = get_file_mut(&mut items, path)?;
+		let start = usize::try_from(offset).map_err(|_| Error::CannotWriteFile)?;
+		let end = start.checked_add(data.len()).ok_or(Error::CannotWriteFile)?;
+		if file.len() < end {
+			file.resize(end, 0);
+		}
+		file[start..end].copy_from_slice(data);
+		Ok(())
+	}
+
+	fn get_length(&self, path: &Path) -> Result<u64, Error> {
+		match self.lock().get(path) {
+			Some(MemoryItem::File(data)) => Ok(data.len() as u64),
+			_ => Err(Error::CannotReadFile),
+		}
+	}
+
+	fn exists(&self, path: &Path) -> bool {
+		self.lock().contains_key(path)
+	}
+
+	fn is_folder(&self, path: &Path) -> bool {
+		matches!(self.lock().get(path), Some(MemoryItem::Folder))
+	}
+
+	fn list_folder(&self, path: &Path) -> Result<Vec<PathBuf>, Error> {
+		let items = self.lock();
+		if !matches!(items.get(path), Some(MemoryItem::Folder)) {
+			return Err(Error::CannotReadFile);
+		}
+		Ok(items.keys().filter(|item_path| item_path.parent() == Some(path)).cloned().collect())
+	}
+
+	fn create_folder(&self, path: &Path) -> Result<(), Error> {
+		let mut items = self.lock();
+		if items.contains_key(path) {
+			return Err(Error::CannotCreateFolder);
+		}
+		// Create the folders that the folder is in
+		for folder_path in path.ancestors().filter(|folder_path| *folder_path != Path::new("")) {
+			match items.get(folder_path) {
+				Some(MemoryItem::Folder) => {}
+				Some(MemoryItem::File(_)) => return Err(Error::CannotCreateFolder),
+				None => {
+					items.insert(folder_path.to_path_buf(), MemoryItem::Folder);
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn remove_file(&self, path: &Path) -> Result<(), Error> {
+		let mut items = self.lock();
+		match items.get(path) {
+			Some(MemoryItem::File(_)) => {
+				items.remove(path);
+				Ok(())
+			}
+			_ => Err(Error::CannotDeleteFile),
+		}
+	}
+
+	fn remove_folder(&self, path: &Path) -> Result<(), Error> {
+		let mut items = self.lock();
+		if !matches!(items.get(path), Some(MemoryItem::Folder)) {
+			return Err(Error::CannotDeleteFile);
+		}
+		for item_path in get_item_paths(&items, path) {
+			items.remove(&item_path);
+		}
+		Ok(())
+	}
+
+	fn rename(&self, from: &Path, to: &Path) -> Result<(), Error> {
+		let mut items = self.lock();
+		if !items.contains_key(from) || items.contains_key(to) || !has_parent_folder(&items, to) || to.starts_with(from) {
+			return Err(Error::CannotRenameFile);
+		}
+		for item_path in get_item_paths(&items, from) {
+			let item = items.remove(&item_path).expect("Paths come from the keys of the items.");
+			let new_item_path = match item_path.strip_prefix(from).expect("Paths were filtered to start with from.") {
+				relative_path if relative_path == Path::new("") => to.to_path_buf(),
+				relative_path => to.join(relative_path),
+			};
+			items.insert(new_item_path, item);
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use std::{env::temp_dir, fs, path::{Path, PathBuf}, process, sync::Arc};
+
+	use super::{FolderStorage, MemoryStorage, Storage};
+
+	/// A folder on disk for a test, removed when dropped.
+	pub(crate) struct TestFolder(pub PathBuf);
+
+	impl TestFolder {
+		pub(crate) fn new(name: &str) -> Self {
+			let path = temp_dir().join(format!("ceolmund_test_{}_{name}", process::id()));
+			fs::remove_dir_all(&path).ok();
+			fs::create_dir_all(&path).unwrap();
+			Self(path)
+		}
+	}
+
+	impl Drop for TestFolder {
+		fn drop(&mut self) {
+			fs::remove_dir_all(&self.0).ok();
+		}
+	}
+
+	/// Run a check against a memory storage and against a folder storage in a folder on disk, giving the check a folder to work in.
+	pub(crate) fn check_both_storages(name: &str, check: impl Fn(Arc<dyn Storage>, &Path)) {
+		let memory_storage = Arc::new(MemoryStorage::new());
+		memory_storage.create_folder(Path::new("root")).unwrap();
+		check(memory_storage, Path::new("root"));
+		let test_folder = TestFolder::new(name);
+		check(Arc::new(FolderStorage), &test_folder.0);
+	}
+
+	#[test]
+	fn write_range_extends_with_zeros() {
+		check_both_storages("write_range", |storage, root| {
+			let path = root.join("file");
+			storage.write_range(&path, 2, &[1, 2]).unwrap();
+			assert_eq!(storage.read(&path).unwrap(), [0, 0, 1, 2]);
+			storage.write_range(&path, 1, &[3]).unwrap();
+			assert_eq!(storage.read(&path).unwrap(), [0, 3, 1, 2]);
+			storage.write_range(&path, 3, &[4, 5]).unwrap();
+			assert_eq!(storage.read(&path).unwrap(), [0, 3, 1, 4, 5]);
+			assert_eq!(storage.get_length(&path).unwrap(), 5);
+			assert_eq!(storage.read_range(&path, 1, 3).unwrap(), [3, 1, 4]);
+			assert!(storage.read_range(&path, 3, 3).is_err());
+			// Files can not be written in folders that do not exist
+			assert!(storage.write_range(&root.join("missing").join("file"), 0, &[1]).is_err());
+		});
+	}
+
+	#[test]
+	fn rename_moves_folders_with_what_is_in_them() {
+		check_both_storages("rename", |storage, root| {
+			storage.create_folder(&root.join("a").join("b")).unwrap();
+			storage.write(&root.join("a").join("b").join("file"), &[1]).unwrap();
+			storage.write(&root.join("a").join("file"), &[2]).unwrap();
+			storage.write(&root.join("ab"), &[3]).unwrap();
+			storage.rename(&root.join("a"), &root.join("c")).unwrap();
+			assert!(!storage.exists(&root.join("a")));
+			assert!(storage.is_folder(&root.join("c").join("b")));
+			assert_eq!(storage.read(&root.join("c").join("b").join("file")).unwrap(), [1]);
+			assert_eq!(storage.read(&root.join("c").join("file")).unwrap(), [2]);
+			// Items that only start with the same characters are not moved
+			assert_eq!(storage.read(&root.join("ab")).unwrap(), [3]);
+			// Renaming fails if there is already something at the new path, nothing is at the old path or the new path is not in a folder
+			assert!(storage.rename(&root.join("ab"), &root.join("c")).is_err());
+			assert!(storage.rename(&root.join("a"), &root.join("d")).is_err());
+			assert!(storage.rename(&root.join("ab"), &root.join("missing").join("ab")).is_err());
+			// Folders can not be moved into themselves
+			assert!(storage.rename(&root.join("c"), &root.join("c").join("b").join("c")).is_err());
+			assert!(storage.is_folder(&root.join("c")));
+		});
+	}
+
+	#[test]
+	fn remove_folder_only_removes_what_is_in_the_folder() {
+		check_both_storages("remove_folder", |storage, root| {
+			storage.create_folder(&root.join("a").join("b")).unwrap();
+			storage.write(&root.join("a").join("b").join("file"), &[1]).unwrap();
+			storage.write(&root.join("ab"), &[2]).unwrap();
+			storage.remove_folder(&root.join("a")).unwrap();
+			assert!(!storage.exists(&root.join("a")));
+			assert!(!storage.exists(&root.join("a").join("b").join("file")));
+			assert_eq!(storage.read(&root.join("ab")).unwrap(), [2]);
+			assert_eq!(storage.list_folder(root).unwrap(), [root.join("ab")]);
+			assert!(storage.remove_folder(&root.join("a")).is_err());
+			assert!(storage.remove_folder(&root.join("ab")).is_err());
+		});
+	}
+}
\ No newline at end of file
@@ -0,0 +1,100 @@
+use std::path::{PathBuf, Path, Component};
+
+use crate::error::Error;
+
+use super::{file_writer::FileWriter, file_reader::FileReader, storage::Storage};
+
+/// The string at the start of world archives.
+const WORLD_ARCHIVE_MAGIC: &str = "ceolmund_world_archive";
+/// The file extension of exported worlds.
+pub const WORLD_ARCHIVE_EXTENSION: &str = "cwa";
+
+/// Pack all the files in a world folder into a single file writer.
+/// The archive is a list of the path of each file relative to the world folder followed by the length of the file and it's contents, ending with an empty path.
+pub fn pack_world_folder(storage: &dyn Storage, world_filepath: &Path) -> Result<FileWriter, Error> {
+	let mut file = FileWriter::new();
+	file.push_str(WORLD_ARCHIVE_MAGIC);
+	pack_folder(storage, world_filepath, "", &mut file)?;
+	file.push_str("");
+	Ok(file)
+}
+
+/// Add all the files in a folder and it's subfolders to an archive.
+fn pack_folder(storage: &dyn Storage, folder_filepath: &Path, relative_path: &str, file: &mut FileWriter) -> Result<(), Error> {
+	for item_filepath in storage.list_folder(folder_filepath)? {
+		let item_name = match item_filepath.file_name().and_then(|item_name| item_name.to_str()) {
+			Some(item_name) => item_name.to_string(),
+			None => continue,
+		};
+		let item_relative_path = match relative_path {
+			"" => item_name.clone(),
+			_ => format!("{relative_path}/{item_name}"),
+		};
+		if storage.is_folder(&item_filepath) {
+			pack_folder(storage, &item_filepath, &item_relative_path, file)?;
+			continue;
+		}
+		// Skip files left over from a save that did not finish.
+		if item_name.ends_with(".tmp") {
+			continue;
+		}
+		let data = storage.read(&item_filepath)?;
+		file.push_string(&item_relative_path);
+		file.push_u64(data.len() as u64);
+		file.data.extend(data);
+	}
+	Ok(())
+}
+
+/// Unpack a world archive into a folder.
+pub fn unpack_world_archive(storage: &dyn Storage, file: &mut FileReader, world_filepath: &Path) -> Result<(), Error> {
+	if file.read_string()? != WORLD_ARCHIVE_MAGIC {
+		return Err(Error::InvalidWorldArchive);
+	}
+	if !storage.is_folder(world_filepath) {
+		storage.create_folder(world_filepath)?;
+	}
+	loop {
+		let relative_path = file.read_string()?;
+		if relative_path.is_empty() {
+			break;
+		}
+		let length = file.read_u64()?;
+		let data = file.read_bytes(length as usize)?;
+		// Only allow paths that stay inside the world folder.
+		let relative_path = PathBuf::from(relative_path);
+		if !relative_path.components().all(|component| matches!(component, Component::Normal(_))) {
+			return Err(Error::InvalidWorldArchive);
+		}
+		let item_filepath = world_filepath.join(relative_path);
+		if let Some(parent_filepath) = item_filepath.parent() {
+			if !storage.is_folder(parent_filepath) {
+				storage.create_folder(parent_filepath)?;
+			}
+		}
+		storage.write(&item_filepath, &data)?;
+	}
+	Ok(())
+}
+
+/// Get a path for an archive in a folder that is not taken, adding a number to the end of the name if the archive for the name exists.
+pub fn get_unused_archive_filepath(storage: &dyn Storage, folder_filepath: &Path, name: &str) -> PathBuf {
+	let mut out = folder_filepath.join(format!("{name}.{WORLD_ARCHIVE_EXTENSION}"));
+	let mut number = 2;
+	while storage.exists(&out) {
+		out = folder_filepath.join(format!("{name} {number}.{WORLD_ARCHIVE_EXTENSION}"));
+		number += 1;
+	}
+	out
+}
+
+/// Get the paths of all exported world files in a folder.
+pub fn list_world_archives(storage: &dyn Storage, folder_filepath: &Path) -> Vec<PathBuf> {
+	let mut out: Vec<PathBuf> = match storage.list_folder(folder_filepath) {
+		Ok(item_filepaths) => item_filepaths.into_iter()
+			.filter(|item_filepath| item_filepath.extension().and_then(|extension| extension.to_str()) == Some(WORLD_ARCHIVE_EXTENSION)).collect(),
+		Err(_) => Vec::new(),
+	};
+	out.sort();
+	out
+}
\ No newline at end of file
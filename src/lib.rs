@@ -0,0 +1,30 @@
+pub mod world;
+pub mod render;
+pub mod io;
+pub mod gui;
+pub mod error;
+pub mod command_line;
+
+#[macro_export]
+macro_rules! const_static_ptr {
+	( $t:ty, $x:expr ) => {
+		{
+			const OUT: $t = $x;
+			&OUT
+		}
+	};
+}
+
+pub fn validate_filename(mut name: String) -> String {
+	name = name.chars().map(|chr| match chr {
+		'/' | '\\' | '<' | '>' | ':' | '\'' | '|' | '?' | '*' | '.' | '~' | '#' | '%' | '&' | '+' | '-' | '{' | '}' | '@' | '"' | '!' | '`' | '=' => '_',
+		_ => chr,
+	}).collect();
+	match name.to_lowercase().as_str() {
+		"con" | "prn" | "aux" | "nul" |
+		"com1" | "com2" | "com3" | "com4" | "com5" | "com6" | "com7" | "com8" | "com9" |
+		"lpt1" | "lpt2" | "lpt3" | "lpt4" | "lpt5" | "lpt6" | "lpt7" | "lpt8" | "lpt9" => name.push('_'),
+		_ => {}
+	}
+	name
+}
\ No newline at end of file
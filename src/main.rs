@@ -1,52 +1,44 @@
 // Don't open a console window when the program starts if not in debug mode.
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-pub mod world;
-pub mod render;
-pub mod io;
-pub mod gui;
-pub mod error;
-
 use std::{io::Cursor, time::Instant};
 
-use gui::gui::GUI;
-use io::{io::IO, game_key::GameKey};
+use ceolmund::{command_line, gui::gui::GUI, io::{io::IO, game_key::GameKey, data_paths::DataPaths}};
 use glium::{glutin::{event_loop::{EventLoop, ControlFlow}, window::{WindowBuilder, Fullscreen}, dpi::LogicalSize, ContextBuilder,
 	event::{Event, WindowEvent, VirtualKeyCode, ElementState, MouseScrollDelta}}, Display, Program, uniforms::{SamplerBehavior, MinifySamplerFilter, MagnifySamplerFilter, Sampler},
 	Blend, DrawParameters, Surface, VertexBuffer, index::{NoIndices, PrimitiveType}, texture::RawImage2d};
 use image::ImageFormat;
 
-#[macro_export]
-macro_rules! const_static_ptr {
-	( $t:ty, $x:expr ) => {
-		{
-			const OUT: $t = $x;
-			&OUT
-		}
-	};
-}
-
-pub fn validate_filename(mut name: String) -> String {
-	name = name.chars().map(|chr| match chr {
-		'/' | '\\' | '<' | '>' | ':' | '\'' | '|' | '?' | '*' | '.' | '~' | '#' | '%' | '&' | '+' | '-' | '{' | '}' | '@' | '"' | '!' | '`' | '=' => '_',
-		_ => chr,
-	}).collect();
-	match name.to_lowercase().as_str() {
-		"con" | "prn" | "aux" | "nul" |
-		"com1" | "com2" | "com3" | "com4" | "com5" | "com6" | "com7" | "com8" | "com9" |
-		"lpt1" | "lpt2" | "lpt3" | "lpt4" | "lpt5" | "lpt6" | "lpt7" | "lpt8" | "lpt9" => name.push('_'),
-		_ => {}
-	}
-	name
-}
-
 const NANOSECONDS_PER_TICK: u128 = 1_000_000_000 / 100;
 
 fn main() {
+	// Get where to keep data from the command line. Throwaway games keep worlds in memory so nothing is saved, the data folder is not looked for so it is not created or moved.
+	let mut args: Vec<String> = std::env::args().skip(1).collect();
+	let path_options = command_line::take_data_path_options(&mut args);
+	let is_throwaway = args == ["throwaway"];
+
 	// Main objects
 	let mut world = None;
 	let mut gui = GUI::new();
-	let mut io = IO::new();
+	let mut io = match is_throwaway {
+		true => IO::new_in_memory(),
+		false => match path_options.and_then(|(data_path, worlds_paths)| DataPaths::new(data_path, worlds_paths).map_err(|error| error.to_string())) {
+			Ok(data_paths) => IO::new(data_paths),
+			Err(error) => {
+				eprintln!("{error}");
+				std::process::exit(1);
+			}
+		}
+	};
+
+	// Run a command instead of starting the game if one was given.
+	if !args.is_empty() && !is_throwaway {
+		if let Err(error) = command_line::run_command(&args, &io) {
+			eprintln!("{error}");
+			std::process::exit(1);
+		}
+		return;
+	}
 
 	// Window
 	let events_loop = EventLoop::new();
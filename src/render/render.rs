@@ -25,8 +25,8 @@ pub fn gui_pos_to_screen_pos(pos: [i16; 2], alignment: GUIAlignment, input: &IO)
 }
 
 /// Converts a world pos and alignment to a y 0-256 screen pos.
-pub fn world_pos_to_render_pos(pos: [i64; 2], offset: [i8; 2]) -> [f32; 2] {
-	[pos[0] as f32 + offset[0] as f32 / 16., pos[1] as f32 + offset[1] as f32 / 16.]
+pub fn world_pos_to_render_pos(pos: [i64; 2], offset: [f32; 2]) -> [f32; 2] {
+	[pos[0] as f32 + offset[0] / 16., pos[1] as f32 + offset[1] / 16.]
 }
 
 /// Converts a GUI size and alignment to a y 0-256 screen size.
@@ -43,6 +43,24 @@ pub enum Texture {
 	FlintAxe,
 	Acorn,
 	PineCone,
+	FishingRod,
+	Bobber,
+	BobberBiting,
+	RawTrout,
+	RawCod,
+	Catfish,
+	Seaweed,
+	OldBoot,
+	Boat,
+	DoorClosed,
+	DoorOpen,
+	GateClosed,
+	GateOpen,
+	OneWayPathNorth,
+	OneWayPathEast,
+	OneWayPathSouth,
+	OneWayPathWest,
+	ShallowWater,
 	//GreenThing,
 	//BlueThing,
 }
@@ -80,6 +98,24 @@ impl Texture {
 			Self::FlintAxe => const_static_ptr!([u16; 4], grid_texture(0x22)),
 			Self::Acorn => const_static_ptr!([u16; 4], grid_texture(0x23)),
 			Self::PineCone => const_static_ptr!([u16; 4], grid_texture(0x24)),
+			Self::FishingRod => const_static_ptr!([u16; 4], grid_texture(0x25)),
+			Self::Bobber => const_static_ptr!([u16; 4], grid_texture(0x26)),
+			Self::BobberBiting => const_static_ptr!([u16; 4], grid_texture(0x27)),
+			Self::RawTrout => const_static_ptr!([u16; 4], grid_texture(0x28)),
+			Self::RawCod => const_static_ptr!([u16; 4], grid_texture(0x29)),
+			Self::Catfish => const_static_ptr!([u16; 4], grid_texture(0x2A)),
+			Self::Seaweed => const_static_ptr!([u16; 4], grid_texture(0x2B)),
+			Self::OldBoot => const_static_ptr!([u16; 4], grid_texture(0x2C)),
+			Self::Boat => const_static_ptr!([u16; 4], grid_texture(0x2D)),
+			Self::DoorClosed => const_static_ptr!([u16; 4], grid_texture(0x2E)),
+			Self::DoorOpen => const_static_ptr!([u16; 4], grid_texture(0x2F)),
+			Self::GateClosed => const_static_ptr!([u16; 4], grid_texture(0x30)),
+			Self::GateOpen => const_static_ptr!([u16; 4], grid_texture(0x31)),
+			Self::OneWayPathNorth => const_static_ptr!([u16; 4], grid_texture(0x32)),
+			Self::OneWayPathEast => const_static_ptr!([u16; 4], grid_texture(0x33)),
+			Self::OneWayPathSouth => const_static_ptr!([u16; 4], grid_texture(0x34)),
+			Self::OneWayPathWest => const_static_ptr!([u16; 4], grid_texture(0x35)),
+			Self::ShallowWater => const_static_ptr!([u16; 4], grid_texture(0x36)),
 			//Self::GreenThing => const_static_ptr!([u16; 4], grid_texture(0xF0)),
 			//Self::BlueThing => const_static_ptr!([u16; 4], grid_texture(0xFF)),
 		}
@@ -117,18 +153,36 @@ impl Texture {
 			Self::FlintShovel => TextureType::Basic,
 			Self::Acorn => TextureType::Basic,
 			Self::PineCone => TextureType::Basic,
+			Self::FishingRod => TextureType::Basic,
+			Self::Bobber => TextureType::Basic,
+			Self::BobberBiting => TextureType::Basic,
+			Self::RawTrout => TextureType::Basic,
+			Self::RawCod => TextureType::Basic,
+			Self::Catfish => TextureType::Basic,
+			Self::Seaweed => TextureType::Basic,
+			Self::OldBoot => TextureType::Basic,
+			Self::Boat => TextureType::Basic,
+			Self::DoorClosed => TextureType::Basic,
+			Self::DoorOpen => TextureType::Basic,
+			Self::GateClosed => TextureType::Basic,
+			Self::GateOpen => TextureType::Basic,
+			Self::OneWayPathNorth => TextureType::Basic,
+			Self::OneWayPathEast => TextureType::Basic,
+			Self::OneWayPathSouth => TextureType::Basic,
+			Self::OneWayPathWest => TextureType::Basic,
+			Self::ShallowWater => TextureType::Basic,
 			//Self::GreenThing => TextureType::Basic,
 			//Self::BlueThing => TextureType::Basic,
 		}
 	}
 
 	/// Render the basic texture getting it's tris.
-	pub fn render_basic(self, tile_pos: [i64; 2], subtile_pos: [i8; 2]) -> [Vertex; 6] {
+	pub fn render_basic(self, tile_pos: [i64; 2], subtile_pos: [f32; 2]) -> [Vertex; 6] {
 		self.render(tile_pos, subtile_pos, false, 0)
 	}
 
 	/// Render an entity texture getting it's tris.
-	pub fn render_entity(self, tile_pos: [i64; 2], subtile_pos: [i8; 2], direction: Direction4, walk_alt_frame: u8) -> [Vertex; 6] {
+	pub fn render_entity(self, tile_pos: [i64; 2], subtile_pos: [f32; 2], direction: Direction4, walk_alt_frame: u8) -> [Vertex; 6] {
 		match self.get_type() {
 			TextureType::Basic => self.render(tile_pos, subtile_pos, false, 0),
 			TextureType::Entity => self.render(tile_pos, subtile_pos, direction == Direction4::West, walk_alt_frame + match direction {
@@ -141,7 +195,7 @@ impl Texture {
 	}
 
 	/// Render the texture
-	fn render(self, tile_pos: [i64; 2], subtile_pos: [i8; 2], reverse: bool, index: u8) -> [Vertex; 6] {
+	fn render(self, tile_pos: [i64; 2], subtile_pos: [f32; 2], reverse: bool, index: u8) -> [Vertex; 6] {
 		let texture_sheet_points = self.get_texture_sheet_points();
 
 		let [start_x, start_y] = world_pos_to_render_pos(tile_pos, subtile_pos);
@@ -1,12 +1,18 @@
-use std::{ops::Range, path::PathBuf};
+use std::{collections::HashMap, ops::Range, path::{Path, PathBuf}, sync::Arc, time::Duration};
 
-use crate::{render::vertex::Vertex, world::tile::tile_stack::TileStack, io::{file_writer::FileWriter, file_reader::FileReader, namespace::Namespace}, error::Error};
+use crate::{render::vertex::Vertex, world::tile::tile_stack::TileStack, io::{file_writer::FileWriter, file_reader::FileReader, namespace::Namespace, region_files::RegionFiles, serializable::Serializable, storage::Storage}, error::Error};
+
+/// How many times to try to save a chunk before giving up and keeping it in memory.
+const SAVE_ATTEMPTS: u8 = 3;
+/// How long to wait after a failed save before trying again.
+const SAVE_RETRY_DELAY: Duration = Duration::from_millis(500);
 
 /// A 64x64 grid of tile stacks
 pub struct Chunk {
 	pub tile_stacks: [Box<[TileStack; 64]>; 64],
 	pub basic_vertices: Vec<Vertex>,
 	pub extra_vertices: Vec<Vertex>,
+	pub needs_saving: bool, // If the chunk has changes that failed to autosave.
 }
 
 impl Chunk {
@@ -54,11 +60,12 @@ impl Chunk {
 			tile_stacks: [(); 64].map(|_| Box::new([(); 64].map(|_| TileStack::new()))),
 			basic_vertices: vertices,
 			extra_vertices: Vec::new(),
+			needs_saving: false,
 		}
 	}
 
 	/// Generate a chunk using the world generator
-	pub fn generate(&mut self, pos: [i64; 2], seed: u32) {
+	pub fn generate(&mut self, pos: [i64; 2], seed: u32, generator_version: u32) {
 		// Get the tile pos of the chunk
 		let tile_x_start = pos[0] * 64;
 		let tile_y_start = pos[1] * 64;
@@ -66,49 +73,91 @@ impl Chunk {
 		for x in 0..64 {
 			for y in 0..64 {
 				// Generate tile
-				self.tile_stacks[y][x].generate([tile_x_start + x as i64, tile_y_start + y as i64], seed);
+				self.tile_stacks[y][x].generate([tile_x_start + x as i64, tile_y_start + y as i64], seed, generator_version);
 			}
 		}
 	}
 
 	/// Load or generate chunk
-	pub async fn get(pos: [i64; 2], chunks_filepath: PathBuf, namespaces_filepath: PathBuf, seed: u32) -> Result<Self, Error> {
+	pub async fn get(pos: [i64; 2], region_files: Arc<RegionFiles>, namespaces_filepath: PathBuf, seed: u32, generator_version: u32) -> Result<Self, Error> {
 		// Create blank chunk
 		let mut out = Self::new_blank();
 		// Try to load chunk otherwise generate said chunk
-		if !out.load(pos, chunks_filepath, namespaces_filepath)? {
-			out.generate(pos, seed);
+		if !out.load(pos, &region_files, namespaces_filepath)? {
+			out.generate(pos, seed, generator_version);
 		}
 		
 		Ok(out)
 	}
 
+	/// Quarantine the data of a chunk that could not be loaded then generate the chunk again.
+	pub async fn recover(pos: [i64; 2], region_files: Arc<RegionFiles>, seed: u32, generator_version: u32, reason: String) -> Result<Self, Error> {
+		region_files.quarantine_chunk(pos, &reason)?;
+		let mut out = Self::new_blank();
+		out.generate(pos, seed, generator_version);
+		Ok(out)
+	}
+
 	/// Load chunk, returning weather it exists or not wrapped in an option that is none when there is an error loading the chunk.
-	pub fn load(&mut self, pos: [i64; 2], chunks_filepath: PathBuf, namespaces_filepath: PathBuf) -> Result<bool, Error> {
-		// Get filepath for chunk and load
-		let mut chunk_filepath = chunks_filepath.clone();
-		chunk_filepath.push(format!("{} {}.cnk", pos[0], pos[1]));
-		let (mut file, _is_version_0) = match FileReader::read_from_file(&chunk_filepath) {
-			Ok(file) => file,
-			Err(_) => return Ok(false),
+	pub fn load(&mut self, pos: [i64; 2], region_files: &RegionFiles, namespaces_filepath: PathBuf) -> Result<bool, Error> {
+		// Get the chunk data from the region file and load
+		let data = match region_files.read_chunk(pos)? {
+			Some(data) => data,
+			None => return Ok(false),
 		};
+		self.deserialize(data, &*region_files.storage, &namespaces_filepath)?;
+		Ok(true)
+	}
+
+	/// Load chunk from the data that it is saved as.
+	pub fn deserialize(&mut self, data: Vec<u8>, storage: &dyn Storage, namespaces_filepath: &Path) -> Result<(), Error> {
+		let (mut file, _is_version_0) = FileReader::read_from_data(data)?;
 		// Get chunk namespace hash
 		let namespace_hash = file.read_u64()?;
 		// Get namespace
-		let namespace = Namespace::load(namespace_hash, namespaces_filepath.clone())?;
+		let namespace = Namespace::load(storage, namespace_hash, namespaces_filepath)?;
 		
 		if namespace.version == 0 {
-			self.load_v0(&mut file, namespace).ok_or(Error::V0Error)?;
-			return Ok(true);
+			return self.load_v0(&mut file, namespace).ok_or(Error::V0Error);
 		}
 
-		for tile_stack_row in &mut self.tile_stacks {
-			for tile_stack in tile_stack_row.iter_mut() {
-				tile_stack.deserialize(&mut file, &namespace, namespace.version)?;
+		match namespace.version {
+			1..=6 => {
+				for tile_stack_row in &mut self.tile_stacks {
+					for tile_stack in tile_stack_row.iter_mut() {
+						*tile_stack = TileStack::deserialize(&mut file, &namespace, namespace.version)?;
+					}
+				}
 			}
+			_ => self.deserialize_palette(&mut file, &namespace)?,
 		}
 		//
-		Ok(true)
+		Ok(())
+	}
+
+	/// Load the tile stacks of a chunk saved as a palette of distinct tile stacks followed by runs of palette indices.
+	fn deserialize_palette(&mut self, file: &mut FileReader, namespace: &Namespace) -> Result<(), Error> {
+		// Get palette
+		let palette_length = file.read_u16()?;
+		let mut palette = Vec::with_capacity(palette_length as usize);
+		for _ in 0..palette_length {
+			palette.push(TileStack::deserialize(file, namespace, namespace.version)?);
+		}
+		// Fill the chunk using the runs
+		let mut tile_stack_index = 0usize;
+		while tile_stack_index < 64 * 64 {
+			let palette_index = file.read_u16()?;
+			let run_length = file.read_u16()? as usize;
+			let tile_stack = palette.get(palette_index as usize).ok_or(Error::PaletteIndexOutOfBounds)?;
+			if run_length == 0 || tile_stack_index + run_length > 64 * 64 {
+				return Err(Error::InvalidChunkRunLength);
+			}
+			for index in tile_stack_index..tile_stack_index + run_length {
+				self.tile_stacks[index / 64][index % 64] = tile_stack.clone();
+			}
+			tile_stack_index += run_length;
+		}
+		Ok(())
 	}
 
 	pub fn load_v0(&mut self, file: &mut FileReader, namespace: Namespace) -> Option<()> {
@@ -129,22 +178,100 @@ impl Chunk {
 		Some(())
 	}
 
-	/// Save chunk
-	pub async fn save(self, pos: [i64; 2], chunks_filepath: PathBuf, namespace_hash: u64) -> Result<(), Error> {
+	/// Weather any tile stack in the chunk has been changed since the chunk was loaded, generated or autosaved.
+	pub fn is_modified(&self) -> bool {
+		self.needs_saving || self.tile_stacks.iter().any(|tile_stack_row| tile_stack_row.iter().any(|tile_stack| tile_stack.is_modified))
+	}
+
+	/// Mark the chunk as being the same as what is saved.
+	pub fn mark_saved(&mut self) {
+		self.needs_saving = false;
+		for tile_stack_row in &mut self.tile_stacks {
+			for tile_stack in tile_stack_row.iter_mut() {
+				tile_stack.is_modified = false;
+			}
+		}
+	}
+
+	/// Save chunk if it has been modified, retrying if the save fails. If the chunk could not be saved then the chunk is given back along with the error.
+	/// Unmodified chunks are discarded as they are the same as what is saved or can be generated again from the seed.
+	pub async fn save(self, pos: [i64; 2], region_files: Arc<RegionFiles>, namespace_hash: u64) -> Result<(), (Self, Error)> {
+		if !self.is_modified() {
+			return Ok(());
+		}
+		let data = match self.serialize(namespace_hash) {
+			Some(data) => data,
+			None => return Err((self, Error::CannotWriteFile)),
+		};
+		match Self::write(pos, region_files, data).await {
+			Ok(()) => Ok(()),
+			Err(error) => Err((self, error)),
+		}
+	}
+
+	/// Get the data that the chunk is saved as.
+	pub fn serialize(&self, namespace_hash: u64) -> Option<Vec<u8>> {
+		self.serialize_file(namespace_hash).write_to_vec()
+	}
+
+	/// Get the contents of a chunk file for the chunk.
+	pub fn serialize_file(&self, namespace_hash: u64) -> FileWriter {
+		Self::serialize_tile_stacks(&self.tile_stacks, namespace_hash)
+	}
+
+	/// Get the contents of a chunk file for the tile stacks of a chunk.
+	pub fn serialize_tile_stacks(tile_stacks: &[Box<[TileStack; 64]>; 64], namespace_hash: u64) -> FileWriter {
 		// Open file
 		let mut file = FileWriter::new();
 		// Push namespace hash
 		file.push_u64(namespace_hash);
-		// Get tile datas
-		for tile_stack_row in &self.tile_stacks {
+		// Build a palette of the distinct tile stacks in the chunk and a list of runs of palette indices
+		let mut palette = FileWriter::new();
+		let mut palette_indices = HashMap::new();
+		let mut runs: Vec<(u16, u16)> = Vec::new();
+		for tile_stack_row in tile_stacks {
 			for tile_stack in tile_stack_row.iter() {
-				tile_stack.serialize(&mut file);
+				let mut tile_stack_data = FileWriter::new();
+				tile_stack.serialize(&mut tile_stack_data);
+				let next_palette_index = palette_indices.len() as u16;
+				let palette_index = *palette_indices.entry(tile_stack_data.data).or_insert_with_key(|tile_stack_data| {
+					palette.data.extend(tile_stack_data);
+					next_palette_index
+				});
+				match runs.last_mut() {
+					Some((last_palette_index, run_length)) if *last_palette_index == palette_index => *run_length += 1,
+					_ => runs.push((palette_index, 1)),
+				}
+			}
+		}
+		// Push palette then runs
+		file.push_u16(palette_indices.len() as u16);
+		file.data.extend(palette.data);
+		for (palette_index, run_length) in runs {
+			file.push_u16(palette_index);
+			file.push_u16(run_length);
+		}
+		file
+	}
+
+	/// Save a copy of the tile stacks of a chunk without freeing the chunk, the tile stacks are serialized in the task so the game is not held up.
+	pub async fn autosave(pos: [i64; 2], region_files: Arc<RegionFiles>, tile_stacks: [Box<[TileStack; 64]>; 64], namespace_hash: u64) -> Result<(), Error> {
+		let data = Self::serialize_tile_stacks(&tile_stacks, namespace_hash).write_to_vec().ok_or(Error::CannotWriteFile)?;
+		Self::write(pos, region_files, data).await
+	}
+
+	/// Write chunk data to the region file, retrying if the write fails.
+	pub async fn write(pos: [i64; 2], region_files: Arc<RegionFiles>, data: Vec<u8>) -> Result<(), Error> {
+		let mut attempt = 1;
+		loop {
+			match region_files.write_chunk(pos, &data) {
+				Ok(()) => return Ok(()),
+				Err(error) if attempt >= SAVE_ATTEMPTS => return Err(error),
+				Err(_) => {
+					attempt += 1;
+					tokio::time::sleep(SAVE_RETRY_DELAY).await;
+				}
 			}
 		}
-		// Get filepath for chunk and save
-		let mut chunk_filepath = chunks_filepath.clone();
-		chunk_filepath.push(format!("{} {}.cnk", pos[0], pos[1]));
-		file.write(&chunk_filepath).ok_or(Error::CannotReadToFile)?;
-		Ok(())
 	}
 }
\ No newline at end of file
@@ -1,22 +1,39 @@
-use std::{collections::HashMap, task::{Context, Poll}, path::PathBuf};
+use std::{collections::HashMap, task::{Context, Poll}, path::PathBuf, sync::Arc};
 
 use futures::FutureExt;
-use tokio::runtime::Runtime;
+use tokio::{runtime::Runtime, task::JoinHandle};
 use noop_waker::noop_waker;
 
-use crate::{render::vertex::Vertex, world::{direction::Direction4, tile::tile_stack::TileStack, entity::{entity::Entity, entity_action_state::EntityActionState}}};
+use crate::{error::Error, io::region_files::RegionFiles, render::vertex::Vertex, world::{direction::Direction4, tile::tile_stack::TileStack, entity::{entity::Entity, entity_action_state::EntityActionState}}};
 
 use super::{chunk_slot::ChunkSlot, chunk::Chunk, chunk_pool_offset::ChunkPoolOffset};
 
+/// How many ticks to wait before loading a chunk again after it could not be read or could not be quarantined.
+const CHUNK_RETRY_TICKS: u16 = 100;
+
+/// What the chunk pool needs to load, generate and save chunks, and where it reports problems with them.
+pub struct ChunkPoolContext<'a> {
+	pub seed: u32,
+	pub generator_version: u32,
+	pub region_files: &'a Arc<RegionFiles>,
+	pub namespaces_filepath: &'a PathBuf,
+	pub namespace_hash: u64,
+	pub warnings: &'a mut Vec<String>,
+}
+
 /// A struct that holds all the chunks weather loaded, loading or freeing.
 pub struct ChunkPool {
 	chunks: HashMap<[i64; 2], ChunkSlot>,
+	autosaving: HashMap<[i64; 2], JoinHandle<Result<(), Error>>>, // Loaded chunks that are being autosaved.
+	pub has_save_failed: bool, // If a chunk has failed to save since the world was loaded.
 }
 
 impl ChunkPool {
 	pub fn new() -> Self {
 		Self {
 			chunks: HashMap::new(),
+			autosaving: HashMap::new(),
+			has_save_failed: false,
 		}
 	}
 
@@ -53,7 +70,8 @@ impl ChunkPool {
 	}
 
 	/// Tick that should always be called even if the game is paused.
-	pub fn tick_always(&mut self, player: Option<&Entity>, player_visable_width: u64, async_runtime: &Runtime, seed: u32, is_freeing: bool, is_freed: &mut bool, chunks_filepath: &PathBuf, namespaces_filepath: &PathBuf, namespace_hash: u64) {
+	pub fn tick_always(&mut self, player: Option<&Entity>, player_visable_width: u64, async_runtime: &Runtime, is_freeing: bool, is_freed: &mut bool, context: ChunkPoolContext) {
+		let ChunkPoolContext { seed, generator_version, region_files, namespaces_filepath, namespace_hash, warnings } = context;
 		// Dummy thread context (used and discarded, wakers are discarded).
 		let waker = noop_waker();
 		let mut cx = Context::from_waker(&waker);
@@ -77,54 +95,150 @@ impl ChunkPool {
 				for x in chunk_x_to_load_start..=chunk_x_to_load_end {
 					let pos = [x, y];
 					if !self.chunks.contains_key(&pos) {
-						self.chunks.insert(pos, ChunkSlot::Getting(async_runtime.spawn(Chunk::get(pos, chunks_filepath.clone(), namespaces_filepath.clone(), seed))));
+						self.chunks.insert(pos, ChunkSlot::Getting(async_runtime.spawn(Chunk::get(pos, region_files.clone(), namespaces_filepath.clone(), seed, generator_version))));
 					}
 				}
 			}
 		}
 
+		let is_in_load_area = |pos: &[i64; 2]| (chunk_x_to_load_start..=chunk_x_to_load_end).contains(&pos[0]) && (chunk_y_to_load_start..=chunk_y_to_load_end).contains(&pos[1]);
+
 		// Run over all loaded chunks.
 		let mut to_free: Vec<[i64; 2]> = Vec::new();
 		let mut to_remove: Vec<[i64; 2]> = Vec::new();
 		for (pos, chunk_slot) in self.chunks.iter_mut() {
 			match chunk_slot {
-				// Free loaded chunks if out of load bounds.
+				// Free loaded chunks if out of load bounds, waiting for the chunk to finish autosaving first.
 				ChunkSlot::Chunk(chunk) => {
-					if (chunk_x_to_load_start..=chunk_x_to_load_end).contains(&pos[0]) && (chunk_y_to_load_start..=chunk_y_to_load_end).contains(&pos[1])
-					&& !is_freeing {
+					if is_in_load_area(pos) && !is_freeing {
 						chunk.tick(pos);
 					}
-					else {
+					else if !self.autosaving.contains_key(pos) {
 						to_free.push(*pos);
 					}
 				}
-				// Unwrap a loading chunk if it has been loaded and add to loaded chunks.
+				// Unwrap a loading chunk if it has been loaded and add to loaded chunks, if it could not be decoded then quarantine and regenerate it.
+				// If the file could not be read or the task did not finish then the chunk is not corrupted so it is loaded again later instead.
 				ChunkSlot::Getting(chunk_getting) => {
 					if let Poll::Ready(chunk) = chunk_getting.poll_unpin(&mut cx) {
-						*chunk_slot = ChunkSlot::Chunk(chunk.unwrap().unwrap());
+						let reason = match chunk {
+							Ok(Ok(chunk)) => {
+								*chunk_slot = ChunkSlot::Chunk(chunk);
+								continue;
+							}
+							Ok(Err(Error::CannotReadFile)) => {
+								warnings.push(format!("Unable to load chunk {} {}: {}", pos[0], pos[1], Error::CannotReadFile));
+								*chunk_slot = ChunkSlot::Waiting(CHUNK_RETRY_TICKS);
+								continue;
+							}
+							Ok(Err(error)) => error.to_string(),
+							Err(error) => {
+								warnings.push(format!("Unable to load chunk {} {}: {error}", pos[0], pos[1]));
+								*chunk_slot = ChunkSlot::Waiting(CHUNK_RETRY_TICKS);
+								continue;
+							}
+						};
+						warnings.push(format!("Chunk {} {} is corrupted and was regenerated.", pos[0], pos[1]));
+						*chunk_slot = ChunkSlot::Recovering(async_runtime.spawn(Chunk::recover(*pos, region_files.clone(), seed, generator_version, reason)));
 					}
 				}
-				// If a chunk is finished freeing then finally delete it.
-				ChunkSlot::Freeing(chunk_freeing) => {
-					if let Poll::Ready(_) = chunk_freeing.poll_unpin(&mut cx) {
+				// If recovering the chunk failed then the chunk is left in the region file and loaded again later.
+				ChunkSlot::Recovering(chunk_recovering) => {
+					if let Poll::Ready(chunk) = chunk_recovering.poll_unpin(&mut cx) {
+						let error = match chunk {
+							Ok(Ok(chunk)) => {
+								*chunk_slot = ChunkSlot::Chunk(chunk);
+								continue;
+							}
+							Ok(Err(error)) => error.to_string(),
+							Err(error) => error.to_string(),
+						};
+						warnings.push(format!("Chunk {} {} could not be quarantined: {error}", pos[0], pos[1]));
+						*chunk_slot = ChunkSlot::Waiting(CHUNK_RETRY_TICKS);
+					}
+				}
+				// Load a waiting chunk again once it has waited, chunks that are no longer needed are removed.
+				ChunkSlot::Waiting(ticks_left) => {
+					if is_freeing || !is_in_load_area(pos) {
 						to_remove.push(*pos);
 					}
+					else if *ticks_left == 0 {
+						*chunk_slot = ChunkSlot::Getting(async_runtime.spawn(Chunk::get(*pos, region_files.clone(), namespaces_filepath.clone(), seed, generator_version)));
+					}
+					else {
+						*ticks_left -= 1;
+					}
+				}
+				// If a chunk is finished freeing then finally delete it, if it failed to save then keep it so saving can be tried again.
+				ChunkSlot::Freeing(chunk_freeing) => {
+					if let Poll::Ready(result) = chunk_freeing.poll_unpin(&mut cx) {
+						match result {
+							Ok(Ok(())) => to_remove.push(*pos),
+							Ok(Err((chunk, error))) => {
+								warnings.push(format!("Unable to save chunk {} {}: {error}", pos[0], pos[1]));
+								self.has_save_failed = true;
+								*chunk_slot = ChunkSlot::Chunk(chunk);
+							}
+							Err(_) => {
+								warnings.push(format!("Chunk {} {} was lost while saving.", pos[0], pos[1]));
+								self.has_save_failed = true;
+								to_remove.push(*pos);
+							}
+						}
+					}
+				}
+			}
+		}
+		// Finish autosaves, if an autosave failed then the chunk is saved again later.
+		let mut autosaved: Vec<[i64; 2]> = Vec::new();
+		for (pos, chunk_autosaving) in self.autosaving.iter_mut() {
+			if let Poll::Ready(result) = chunk_autosaving.poll_unpin(&mut cx) {
+				autosaved.push(*pos);
+				let error = match result {
+					Ok(Ok(())) => continue,
+					Ok(Err(error)) => error.to_string(),
+					Err(error) => error.to_string(),
+				};
+				warnings.push(format!("Unable to autosave chunk {} {}: {error}", pos[0], pos[1]));
+				self.has_save_failed = true;
+				if let Some(ChunkSlot::Chunk(chunk)) = self.chunks.get_mut(pos) {
+					chunk.needs_saving = true;
 				}
 			}
 		}
+		for pos in autosaved.iter() {
+			self.autosaving.remove(pos);
+		}
 		for pos in to_free.iter() {
 			if let ChunkSlot::Chunk(chunk) = self.chunks.remove(pos).unwrap() {
-				self.chunks.insert(*pos, ChunkSlot::Freeing(async_runtime.spawn(chunk.save(*pos, chunks_filepath.clone(), namespace_hash))));
+				self.chunks.insert(*pos, ChunkSlot::Freeing(async_runtime.spawn(chunk.save(*pos, region_files.clone(), namespace_hash))));
 			}
 		}
 		for pos in to_remove.iter() {
 			self.chunks.remove(pos);
 		}
-		if is_freeing && self.chunks.len() == 0 {
+		if is_freeing && self.chunks.len() == 0 && self.autosaving.is_empty() {
 			*is_freed = true;
 		}
 	}
 
+	/// Start saving all loaded chunks that have been modified in the background without freeing them.
+	pub fn autosave(&mut self, async_runtime: &Runtime, region_files: &Arc<RegionFiles>, namespace_hash: u64) {
+		for (pos, chunk_slot) in self.chunks.iter_mut() {
+			let chunk = match chunk_slot {
+				ChunkSlot::Chunk(chunk) => chunk,
+				_ => continue,
+			};
+			if !chunk.is_modified() || self.autosaving.contains_key(pos) {
+				continue;
+			}
+			// The chunk is serialized in the task from a copy of it's tile stacks, if the autosave fails then the chunk is marked as needing saving again
+			let tile_stacks = chunk.tile_stacks.clone();
+			chunk.mark_saved();
+			self.autosaving.insert(*pos, async_runtime.spawn(Chunk::autosave(*pos, region_files.clone(), tile_stacks, namespace_hash)));
+		}
+	}
+
 	/// Get the tile stack at the world pos wrapped in Some if the chunk it is in is loaded, else get None.
 	pub fn get_tile_stack_at_mut(&mut self, pos: [i64; 2]) -> Option<&mut TileStack> {
 		let chunk_slot = self.chunks.get_mut(&[pos[0].div_euclid(64), pos[1].div_euclid(64)])?;
@@ -139,4 +253,79 @@ impl ChunkPool {
 			offset,
 		}
 	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{path::{Path, PathBuf}, sync::Arc, thread::sleep, time::Duration};
+
+	use tokio::runtime::Runtime;
+
+	use crate::{io::{load_fuzzing::get_current_namespace_hash, namespace::Namespace, region_files::RegionFiles, storage::{MemoryStorage, Storage}}, world::{chunk::chunk::Chunk, difficulty::Difficulty, entity::entity::Entity, tile::tile::Tile}};
+
+	use super::{ChunkPool, ChunkPoolContext, CHUNK_RETRY_TICKS};
+
+	/// Tick a chunk pool with a new player at 0, 0, getting the warnings from the tick.
+	fn tick(chunk_pool: &mut ChunkPool, async_runtime: &Runtime, region_files: &Arc<RegionFiles>) -> Vec<String> {
+		let player = Entity::new_player(Difficulty::Easy);
+		let mut warnings = Vec::new();
+		let mut is_freed = false;
+		let context = ChunkPoolContext { seed: 0, generator_version: 0, region_files, namespaces_filepath: &PathBuf::from("namespaces"), namespace_hash: 0, warnings: &mut warnings };
+		chunk_pool.tick_always(Some(&player), 0, async_runtime, false, &mut is_freed, context);
+		sleep(Duration::from_millis(5));
+		warnings
+	}
+
+	#[test]
+	fn unreadable_chunks_are_not_loaded_again_every_tick() {
+		let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+		let region_files = Arc::new(RegionFiles::new(storage.clone(), PathBuf::from("chunks")));
+		// Region files that are folders exist but cannot be read
+		for region_pos in [[-1, -1], [0, -1], [-1, 0], [0, 0]] {
+			storage.create_folder(&region_files.get_region_filepath(region_pos)).unwrap();
+		}
+		let async_runtime = Runtime::new().unwrap();
+		let mut chunk_pool = ChunkPool::new();
+		let mut warnings = Vec::new();
+		for _ in 0..CHUNK_RETRY_TICKS / 2 {
+			warnings.extend(tick(&mut chunk_pool, &async_runtime, &region_files));
+		}
+		// Each of the 3x3 chunks around the player is read once
+		assert_eq!(warnings.len(), 9);
+		assert!(warnings.iter().all(|warning| warning.starts_with("Unable to load chunk")));
+		assert!(!storage.exists(&Path::new("chunks").join("quarantine")));
+	}
+
+	#[test]
+	fn autosaved_chunks_are_saved_as_they_were_when_autosaved() {
+		let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+		storage.create_folder(Path::new("chunks")).unwrap();
+		storage.create_folder(Path::new("namespaces")).unwrap();
+		let namespace_hash = get_current_namespace_hash();
+		Namespace::current().serialize().write(&*storage, &Path::new("namespaces").join(format!("{namespace_hash:0>16x}.nsp"))).unwrap();
+		let region_files = Arc::new(RegionFiles::new(storage.clone(), PathBuf::from("chunks")));
+		// Save blank chunks so they are loaded instead of generated
+		for y in -1..=1 {
+			for x in -1..=1 {
+				region_files.write_chunk([x, y], &Chunk::new_blank().serialize(namespace_hash).unwrap()).unwrap();
+			}
+		}
+		let async_runtime = Runtime::new().unwrap();
+		let mut chunk_pool = ChunkPool::new();
+		while chunk_pool.get_tile_stack_at_mut([1, 2]).is_none() {
+			assert!(tick(&mut chunk_pool, &async_runtime, &region_files).is_empty());
+		}
+		let tile_stack = chunk_pool.get_tile_stack_at_mut([1, 2]).unwrap();
+		tile_stack.tiles = vec![Tile::Sand];
+		tile_stack.mark_modified();
+		chunk_pool.autosave(&async_runtime, &region_files, namespace_hash);
+		// Changes made after the autosave starts are not saved by it
+		chunk_pool.get_tile_stack_at_mut([1, 2]).unwrap().tiles.push(Tile::Boat);
+		while !chunk_pool.autosaving.is_empty() {
+			assert!(tick(&mut chunk_pool, &async_runtime, &region_files).is_empty());
+		}
+		let mut chunk = Chunk::new_blank();
+		chunk.deserialize(region_files.read_chunk([0, 0]).unwrap().unwrap(), &*storage, Path::new("namespaces")).unwrap();
+		assert!(chunk.tile_stacks[2][1].tiles == [Tile::Sand]);
+	}
 }
\ No newline at end of file
@@ -4,11 +4,13 @@ use crate::error::Error;
 
 use super::chunk::Chunk;
 
-/// A chunk slot, can be a chunk, a generating chunk or a freeing chunk.
+/// A chunk slot, can be a chunk, a generating chunk, a chunk being regenerated after failing to load, a chunk waiting to be loaded again after it could not be read or a freeing chunk.
 pub enum ChunkSlot {
 	Chunk(Chunk),
 	Getting(JoinHandle<Result<Chunk, Error>>),
-	Freeing(JoinHandle<Result<(), Error>>)
+	Recovering(JoinHandle<Result<Chunk, Error>>),
+	Waiting(u16), // Ticks until the chunk is loaded again.
+	Freeing(JoinHandle<Result<(), (Chunk, Error)>>)
 }
 
 impl ChunkSlot {
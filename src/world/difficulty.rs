@@ -1,9 +1,10 @@
-use strum::IntoEnumIterator;
-use std::collections::HashMap;
-
+use ceolmund_derive::Serializable;
 use strum_macros::{EnumIter, EnumCount};
 
-#[derive(EnumCount, EnumIter, Copy, Clone, Eq, PartialEq)]
+use crate::io::registry::Registered;
+
+#[derive(EnumCount, EnumIter, Copy, Clone, Eq, PartialEq, Serializable)]
+#[serializable(namespace = difficulties)]
 pub enum Difficulty {
 	Sandbox,
 	Easy,
@@ -11,8 +12,10 @@ pub enum Difficulty {
 	Hard,
 }
 
-impl Difficulty {
-	pub const fn get_name_id(self) -> &'static str {
+impl Registered for Difficulty {
+	const CATEGORY: &'static str = "difficulty";
+
+	fn get_name_id(self) -> &'static str {
 		match self {
 			Self::Sandbox => "sandbox",
 			Self::Easy => "easy",
@@ -20,12 +23,4 @@ impl Difficulty {
 			Self::Hard => "hard",
 		}
 	}
-
-	pub fn get_name_map() -> HashMap<String, Self> {
-		let mut out = HashMap::new();
-		for difficulty in Self::iter() {
-			out.insert(difficulty.get_name_id().to_string(), difficulty);
-		}
-		out
-	}
 }
\ No newline at end of file
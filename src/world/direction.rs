@@ -1,9 +1,10 @@
-use std::collections::HashMap;
-
-use strum::IntoEnumIterator;
+use ceolmund_derive::Serializable;
 use strum_macros::{EnumIter, EnumCount};
 
-#[derive(PartialEq, Eq, Clone, Copy, EnumCount, EnumIter, Debug)]
+use crate::io::registry::Registered;
+
+#[derive(PartialEq, Eq, Clone, Copy, EnumCount, EnumIter, Debug, Serializable)]
+#[serializable(namespace = direction_4s)]
 /// North/East/South/West direction.
 pub enum Direction4 {
 	North,
@@ -12,8 +13,10 @@ pub enum Direction4 {
 	West,
 }
 
-impl Direction4 {
-	pub const fn get_name_id(self) -> &'static str {
+impl Registered for Direction4 {
+	const CATEGORY: &'static str = "direction_4";
+
+	fn get_name_id(self) -> &'static str {
 		match self {
 			Self::North => "north",
 			Self::East => "east",
@@ -21,12 +24,4 @@ impl Direction4 {
 			Self::West => "west",
 		}
 	}
-
-	pub fn get_name_map() -> HashMap<String, Self> {
-		let mut out = HashMap::new();
-		for tile in Self::iter() {
-			out.insert(tile.get_name_id().to_string(), tile);
-		}
-		out
-	}
 }
\ No newline at end of file
@@ -1,7 +1,12 @@
-use std::path::PathBuf;
+use std::path::Path;
 
-use crate::{render::vertex::Vertex, io::{game_key::GameKey, io::IO, file_writer::FileWriter, file_reader::FileReader, namespace::Namespace}, world::{direction::Direction4, chunk::chunk_pool::ChunkPool, item::{item::Item, inventory::Inventory}, difficulty::Difficulty}, gui::{gui::GUI, gui_menu::GUIMenu, gui_menu_variant::GUIMenuVariant}, error::Error};
-use super::{entity_action_state::EntityActionState, entity_type::{EntityType, EntityVariant}};
+use crate::{render::{vertex::Vertex, texture::Texture}, io::{game_key::GameKey, io::IO, file_writer::FileWriter, file_reader::FileReader, namespace::Namespace, record::Record, serializable::Serializable, storage::Storage}, world::{direction::Direction4, chunk::chunk_pool::ChunkPool, item::{item::Item, inventory::Inventory, fishing_loot_table::{FishingLootTable, FISH_BITE_WINDOW}}, difficulty::Difficulty, tile::tile::Tile}, gui::{gui::GUI, gui_menu::GUIMenu, gui_menu_variant::GUIMenuVariant}, error::Error};
+use super::{entity_action_state::{EntityActionState, WALK_PROGRESS_PER_TILE}, entity_type::{EntityType, EntityVariant}};
+
+/// How much stamina is used each tick when sprinting.
+const STAMINA_DRAIN_PER_TICK: u16 = 2;
+/// How much stamina is regained each tick when standing still.
+const STAMINA_REGENERATION_PER_TICK: u16 = 1;
 
 /// A world object that is can move from tile to tile.
 pub struct Entity {
@@ -13,26 +18,62 @@ pub struct Entity {
 }
 
 impl Entity {
-	/// Get how many pixels an entity is offset from a tile (when walking).
-	pub fn get_subtile_pos(&self) -> [i8; 2] {
+	/// Get how many pixels an entity is offset from a tile (when walking), can be a fraction of a pixel so that walking at any speed is smooth.
+	pub fn get_subtile_pos(&self) -> [f32; 2] {
 		match self.action_state {
-			EntityActionState::Idle => [0, 0],
-			EntityActionState::Walking(walking_direction, walked_amount) => match walking_direction {
-				Direction4::North => [0, -(walked_amount as i8)],
-				Direction4::East => [(walked_amount as i8), 0],
-				Direction4::South => [0, (walked_amount as i8)],
-				Direction4::West => [-(walked_amount as i8), 0],
+			EntityActionState::Idle | EntityActionState::Fishing(..) => [0., 0.],
+			EntityActionState::Walking(walking_direction, walked_amount) => {
+				let walked_pixels = walked_amount as f32 / 16.;
+				match walking_direction {
+					Direction4::North => [0., -walked_pixels],
+					Direction4::East => [walked_pixels, 0.],
+					Direction4::South => [0., walked_pixels],
+					Direction4::West => [-walked_pixels, 0.],
+				}
 			}
 		}
 	}
 
+	/// Weather the entity has the stamina and health to sprint, in hard mode hurt entities cannot sprint.
+	pub fn can_sprint(&self, difficulty: Difficulty) -> bool {
+		let stamina = match self.entity_type {
+			EntityType::Player { stamina, .. } => stamina,
+		};
+		let is_hurt = self.health < EntityVariant::from(&self.entity_type).max_health() / 2;
+		stamina > 0 && !(difficulty == Difficulty::Hard && is_hurt)
+	}
+
+	/// Get how much walk progress the entity makes each tick from the walk speed of the tile stack it is on.
+	pub fn get_walk_speed(&self, tile_stack_walk_speed: u16) -> u16 {
+		match self.entity_type {
+			EntityType::Player { is_sprinting: true, .. } => tile_stack_walk_speed * 3 / 2,
+			_ => tile_stack_walk_speed,
+		}
+	}
+
 	/// A tick for the player that reads the io input.
-	pub fn player_tick(&mut self, chunks: &mut ChunkPool, input: &IO, gui: &mut GUI, difficulty: Difficulty) {
+	pub fn player_tick(&mut self, chunks: &mut ChunkPool, input: &IO, gui: &mut GUI, difficulty: Difficulty, seed: u32, is_night: bool) {
 		let pos_in_front = self.get_pos_in_front();
 		if input.get_game_key_starting_now(GameKey::MenuOpenClose) {
 			gui.menus.push(GUIMenu::new(GUIMenuVariant::Paused));
 		}
 		let action_state = self.action_state.clone();
+		// Sprint while the sprint key is held and the player is able to.
+		let can_sprint = self.can_sprint(difficulty);
+		match &mut self.entity_type {
+			EntityType::Player { is_sprinting, stamina, .. } => {
+				*is_sprinting = input.get_game_key(GameKey::Sprint) && can_sprint;
+				// Drain stamina while sprinting and regenerate it while standing still.
+				if difficulty != Difficulty::Sandbox {
+					match action_state {
+						EntityActionState::Walking(..) if *is_sprinting => *stamina = stamina.saturating_sub(STAMINA_DRAIN_PER_TICK),
+						EntityActionState::Idle | EntityActionState::Fishing(..) =>
+							*stamina = stamina.saturating_add(STAMINA_REGENERATION_PER_TICK).min(EntityVariant::Player.max_stamina()),
+						_ => {}
+					}
+				}
+			}
+		}
 		if action_state == EntityActionState::Idle {
 			// Change item
 			let (inventory, selected_item) = match &mut self.entity_type {
@@ -59,8 +100,25 @@ impl Entity {
 			if input.get_game_key_starting_now(GameKey::Interact) || (input.get_game_key(GameKey::Turbo) && input.get_game_key(GameKey::Interact)) {
 				// Get the item stack selected.
 				let item_stack = &mut inventory.items[*selected_item as usize];
+				// Cast the fishing rod if the player is facing water.
+				if item_stack.0 == Item::FishingRod {
+					if let Some(tile_stack) = chunks_offset.get_origin_tile_stack_mut() {
+						if tile_stack.tiles.last() == Some(&Tile::Water) {
+							self.action_state = EntityActionState::Fishing(FishingLootTable::at_pos(pos_in_front, seed).roll_bite_wait());
+						}
+					}
+					return;
+				}
+				// Open or close doors and gates with an empty hand.
+				if item_stack.0.is_none() && input.get_game_key_starting_now(GameKey::Interact) {
+					if let Some(tile_stack) = chunks_offset.get_origin_tile_stack_mut() {
+						if tile_stack.interact() {
+							return;
+						}
+					}
+				}
 				// Use the item and get back drops.
-				let (consume_item, drops) = Item::use_stack_mut_self(item_stack, &mut chunks_offset);
+				let (consume_item, drops) = Item::use_stack_mut_self(item_stack, &mut chunks_offset, self.facing);
 				// Consume item and get drops if not in sandbox mode.
 				if difficulty != Difficulty::Sandbox {
 					if consume_item {
@@ -106,23 +164,107 @@ impl Entity {
 				}
 			}
 		}
+		if let EntityActionState::Fishing(ticks_left) = action_state {
+			// Stop fishing if a walk key is pressed or the water has gone.
+			let is_facing_water = match chunks.get_tile_stack_at_mut(pos_in_front) {
+				Some(tile_stack) => tile_stack.tiles.last() == Some(&Tile::Water),
+				None => false,
+			};
+			if !is_facing_water || [GameKey::WalkNorth, GameKey::WalkEast, GameKey::WalkSouth, GameKey::WalkWest].iter().any(|key| input.get_game_key_starting_now(*key)) {
+				self.action_state = EntityActionState::Idle;
+				return;
+			}
+			// Reel in the line, catching the fish if it is biting.
+			if input.get_game_key_starting_now(GameKey::Interact) {
+				self.action_state = EntityActionState::Idle;
+				if ticks_left > FISH_BITE_WINDOW {
+					return;
+				}
+				let inventory = match &mut self.entity_type {
+					EntityType::Player { inventory, .. } => inventory,
+				};
+				let caught = FishingLootTable::at_pos(pos_in_front, seed).roll(is_night);
+				// Drop items that cannot be added to the players inventory on the floor at the tile the player is standing on.
+				let to_drop_on_floor = inventory.add_items((caught, 1));
+				if let Some(tile_stack) = chunks.get_tile_stack_at_mut(self.pos) {
+					tile_stack.drop_item_onto(to_drop_on_floor);
+					tile_stack.mark_modified();
+				}
+			}
+		}
+	}
+
+	/// Weather the entity is in a boat.
+	pub fn is_boating(&self) -> bool {
+		match self.entity_type {
+			EntityType::Player { is_boating, .. } => is_boating,
+		}
+	}
+
+	/// Get into a boat when moving onto one and leave the boat behind when moving from water onto land.
+	fn board_or_leave_boat(&mut self, chunks: &mut ChunkPool, last_pos: [i64; 2]) {
+		let is_boating = match &mut self.entity_type {
+			EntityType::Player { is_boating, .. } => is_boating,
+		};
+		let tile_stack = match chunks.get_tile_stack_at_mut(self.pos) {
+			Some(tile_stack) => tile_stack,
+			None => return,
+		};
+		if !*is_boating {
+			if tile_stack.tiles.last() == Some(&Tile::Boat) {
+				tile_stack.tiles.pop();
+				tile_stack.mark_modified();
+				*is_boating = true;
+			}
+			return;
+		}
+		if tile_stack.tiles.last() == Some(&Tile::Water) {
+			return;
+		}
+		if let Some(last_tile_stack) = chunks.get_tile_stack_at_mut(last_pos) {
+			last_tile_stack.tiles.push(Tile::Boat);
+			last_tile_stack.mark_modified();
+			*is_boating = false;
+		}
 	}
 
 	/// A tick for all entities.
 	pub fn tick(&mut self, chunks: &mut ChunkPool) {
+		// Get how fast the entity is walking, the first half of a step is on the tile stack being left and the second half is on the tile stack being entered.
+		let walk_speed = match self.action_state {
+			EntityActionState::Walking(direction, amount) => {
+				let tile_stack_pos = match amount < WALK_PROGRESS_PER_TILE / 2 {
+					true => self.pos,
+					false => self.get_pos_in_direction(direction),
+				};
+				let tile_stack_walk_speed = match chunks.get_tile_stack_at_mut(tile_stack_pos) {
+					Some(tile_stack) => tile_stack.get_walk_speed(),
+					None => 16,
+				};
+				self.get_walk_speed(tile_stack_walk_speed)
+			}
+			_ => 0,
+		};
 		match &mut self.action_state {
 			EntityActionState::Idle => {},
-			EntityActionState::Walking(direction, amount) => {
-				if *amount < 16 {
-					*amount += 1;
+			// The fish gets away if it is not reeled in while biting.
+			EntityActionState::Fishing(ticks_left) => {
+				*ticks_left = ticks_left.saturating_sub(1);
+				if *ticks_left == 0 {
+					self.action_state = EntityActionState::Idle;
 				}
-				if *amount > 15 {
+			}
+			EntityActionState::Walking(direction, amount) => {
+				*amount = amount.saturating_add(walk_speed).min(WALK_PROGRESS_PER_TILE);
+				if *amount == WALK_PROGRESS_PER_TILE {
 					let direction = *direction;
 					let pos = self.get_pos_in_direction(direction);
+					let last_pos = self.pos;
 					if let Some(tile) = chunks.get_tile_stack_at_mut(self.pos) {
 						self.pos = pos;
 						self.action_state = EntityActionState::Idle;
 						tile.entity_move_to(self);
+						self.board_or_leave_boat(chunks, last_pos);
 					}
 				}
 			}
@@ -154,10 +296,22 @@ impl Entity {
 	/// Get a vertex of tris for the entity.
 	pub fn render(&self, vertices_in_out: &mut Vec<Vertex>) {
 		let texture = self.entity_type.get_texture();
+		// Draw the boat under the entity.
+		if self.is_boating() {
+			vertices_in_out.extend(Texture::Boat.render_basic(self.pos, self.get_subtile_pos()));
+		}
 		vertices_in_out.extend(texture.render_entity(self.pos, self.get_subtile_pos(), self.facing, match self.action_state {
-			EntityActionState::Walking(_walking_direction, amount) => amount / 8 + 1,
-			EntityActionState::Idle => 0,
+			EntityActionState::Walking(_walking_direction, amount) => (amount / (WALK_PROGRESS_PER_TILE / 2)) as u8 + 1,
+			EntityActionState::Idle | EntityActionState::Fishing(..) => 0,
 		}));
+		// Draw the bobber on the water in front of the entity.
+		if let EntityActionState::Fishing(ticks_left) = self.action_state {
+			let bobber_texture = match ticks_left > FISH_BITE_WINDOW {
+				true => Texture::Bobber,
+				false => Texture::BobberBiting,
+			};
+			vertices_in_out.extend(bobber_texture.render_basic(self.get_pos_in_front(), [0., 0.]));
+		}
 	}
 
 	/// Create a neew player at 0, 0
@@ -167,70 +321,86 @@ impl Entity {
 			pos: [0, 0],
 			action_state: EntityActionState::Idle,
 			facing: Direction4::South,
-			entity_type: EntityType::Player { inventory, selected_item: 0, respawn_pos: [0, 0], is_swaping_item: false },
+			entity_type: EntityType::Player { inventory, selected_item: 0, respawn_pos: [0, 0], is_swaping_item: false, is_boating: false, is_sprinting: false, stamina: EntityVariant::Player.max_stamina() },
 			health: 100,
 		}
 	}
 
 	/// Save player to file
-	pub fn save_player(&self, player_filepath: &PathBuf, namespace_hash: u64) -> Option<()> {
+	pub fn save_player(&self, storage: &dyn Storage, player_filepath: &Path, namespace_hash: u64) -> Result<(), Error> {
+		self.serialize_player(namespace_hash).write(storage, player_filepath)
+	}
+
+	/// Get the contents of the player file for the player.
+	pub fn serialize_player(&self, namespace_hash: u64) -> FileWriter {
 		// Open file
 		let mut file = FileWriter::new();
 		// Push namespace hash
 		file.data.extend(namespace_hash.to_le_bytes());
 		// Get entity data
 		self.serialize(&mut file);
-		// Write
-		file.write(player_filepath)?;
-		Some(())
+		file
 	}
 
 	// Load player from file
-	pub fn load_player(player_filepath: &PathBuf, namespaces_filepath: &PathBuf, difficulty: Difficulty) -> Result<Self, Error> {
+	pub fn load_player(storage: &dyn Storage, player_filepath: &Path, namespaces_filepath: &Path) -> Result<Self, Error> {
 		// Open file
-		let (mut file, _is_version_0) = FileReader::read_from_file(player_filepath)?;
+		let (mut file, _is_version_0) = FileReader::read_from_file(storage, player_filepath)?;
 		// Get namespace
 		let namespace_hash = file.read_u64()?;
-		let namespace = Namespace::load(namespace_hash, namespaces_filepath.clone())?;
+		let namespace = Namespace::load(storage, namespace_hash, namespaces_filepath)?;
 		// Load entity
-		Ok(Self::deserialize(&mut file, &namespace, namespace.version, difficulty)?)
-	}
-
-	/// Save an entity
-	pub fn serialize(&self, file: &mut FileWriter) {
-		// Push pos
-		file.push_world_pos(self.pos);
-		// Push facing
-		file.push_u8(self.facing as u8);
-		// Push action state
-		self.action_state.serialize(file);
-		// Push type
+		Self::deserialize(&mut file, &namespace, namespace.version)
+	}
+}
+
+/// Entities are saved as a record of fields, entities saved before version 9 are saved as their fields in order.
+impl Serializable for Entity {
+	fn serialize(&self, file: &mut FileWriter) {
+		file.push_value_field("pos", &self.pos);
+		file.push_value_field("facing", &self.facing);
+		file.push_value_field("action_state", &self.action_state);
+		file.push_value_field("health", &self.health);
+		// Push type and the fields for the type
 		self.entity_type.serialize(file);
-		// Push health
-		file.push_u32(self.health);
-	}
-
-	pub fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32, difficulty: Difficulty) -> Result<Self, Error> {
-		// Get pos
-		let pos = file.read_world_pos()?;
-		// Get facing
-		let facing = *namespace.direction_4s.get(file.read_u8()? as usize).ok_or(Error::IDOutOfNamespaceBounds)?;
-		// Get action state
-		let action_state = EntityActionState::deserialize(file, namespace, version, facing)?;
-		// Get entity type
-		let entity_type = EntityType::deserialize(file, namespace, version, difficulty)?;
-		// Get health
-		let health = match version {
-			0 => EntityVariant::Player.max_health(),
-			_ => file.read_u32()?,
-		};
+		file.push_record_end();
+	}
 
-		Ok(Self {
-			pos,
-			facing,
-			action_state,
-			entity_type,
-			health,
-		})
+	fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<Self, Error> {
+		let mut entity = match version {
+			// Entities saved from version 9 are a record of fields
+			9.. => {
+				let record = Record::deserialize(file)?;
+				let entity_type = EntityType::deserialize_record(&record, namespace, version)?;
+				Self {
+					pos: record.read_value("pos", namespace, version)?.unwrap_or([0, 0]),
+					facing: record.read_value("facing", namespace, version)?.unwrap_or(Direction4::South),
+					action_state: record.read_value("action_state", namespace, version)?.unwrap_or(EntityActionState::Idle),
+					health: record.read_value("health", namespace, version)?.unwrap_or(EntityVariant::from(&entity_type).max_health()),
+					entity_type,
+				}
+			}
+			_ => {
+				let pos = <[i64; 2]>::deserialize(file, namespace, version)?;
+				let facing = Direction4::deserialize(file, namespace, version)?;
+				let action_state = EntityActionState::deserialize(file, namespace, version)?;
+				let entity_type = EntityType::deserialize_v8(file, namespace, version)?;
+				let health = u32::deserialize_since(file, namespace, version, 1, || EntityVariant::from(&entity_type).max_health())?;
+				Self {
+					pos,
+					facing,
+					action_state,
+					entity_type,
+					health,
+				}
+			}
+		};
+		// Entities saved before version 2 walk in the direction they are facing
+		if version < 2 {
+			if let EntityActionState::Walking(moving_direction, _) = &mut entity.action_state {
+				*moving_direction = entity.facing;
+			}
+		}
+		Ok(entity)
 	}
 }
\ No newline at end of file
@@ -1,65 +1,62 @@
-use std::collections::HashMap;
-
-use strum::{IntoEnumIterator};
 use strum_macros::{EnumDiscriminants, EnumCount, EnumIter};
 
-use crate::{io::{namespace::Namespace, file_reader::FileReader, file_writer::FileWriter}, world::direction::Direction4, error::Error};
+use crate::{io::{namespace::Namespace, file_reader::FileReader, file_writer::FileWriter, serializable::Serializable, registry::Registered}, world::direction::Direction4, error::Error};
+
+/// How much walk progress it takes to move one tile, 16 progress is one pixel.
+pub const WALK_PROGRESS_PER_TILE: u16 = 256;
 
 #[derive(Eq, PartialEq, Clone)]
 #[derive(EnumDiscriminants)]
 #[strum_discriminants(name(EntityActionStateVariant), derive(EnumCount, EnumIter))]
-#[repr(u8)]
 pub enum EntityActionState {
 	Idle,
-	Walking(Direction4, u8),
+	Walking(Direction4, u16), // Direction and walk progress.
+	Fishing(u16), // Ticks left until the fish on the line gets away.
 }
 
-impl EntityActionState {
-	/// Save
-	pub fn serialize(&self, file: &mut FileWriter) {
+/// Files saved before version 2 do not have the direction of a walking entity saved, entities loaded from them should walk in the direction that they are facing.
+impl Serializable for EntityActionState {
+	fn serialize(&self, file: &mut FileWriter) {
 		// Push id
-		file.push_u8(EntityActionStateVariant::from(self) as u8);
+		file.push_id(EntityActionStateVariant::from(self) as usize);
 		
 		match self {
 			Self::Idle => {}
 			Self::Walking(moving_direction, amount) => {
-				file.push_u8(*moving_direction as u8);
-				file.push_u8(*amount);
+				moving_direction.serialize(file);
+				amount.serialize(file);
 			},
+			Self::Fishing(ticks_left) => ticks_left.serialize(file),
 		}
 	}
 
-	/// Load
-	pub fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32, facing: Direction4) -> Result<Self, Error> {
-		let variant = *namespace.entity_action_states.get(file.read_u8()? as usize).ok_or(Error::IDOutOfNamespaceBounds)?;
-		Ok(match variant {
+	fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<Self, Error> {
+		Ok(match namespace.entity_action_states.read(file, version)? {
 			EntityActionStateVariant::Idle => Self::Idle,
 			EntityActionStateVariant::Walking => {
-				let moving_direction = if version < 2 {
-					facing
-				}
-				else {
-					*namespace.direction_4s.get(file.read_u8()? as usize).ok_or(Error::IDOutOfNamespaceBounds)?
+				let moving_direction = Direction4::deserialize_since(file, namespace, version, 2, || Direction4::South)?;
+				let amount = match version {
+					0..=4 => file.read_u8()? as u16 * 16,
+					_ => file.read_u16()?,
 				};
-				Self::Walking(moving_direction, file.read_u8()?)
+				if amount > WALK_PROGRESS_PER_TILE {
+					return Err(Error::InvalidFieldValue("walk progress"));
+				}
+				Self::Walking(moving_direction, amount)
 			},
+			EntityActionStateVariant::Fishing => Self::Fishing(u16::deserialize(file, namespace, version)?),
 		})
 	}
 }
 
-impl EntityActionStateVariant {
-	pub const fn get_name_id(self) -> &'static str {
+impl Registered for EntityActionStateVariant {
+	const CATEGORY: &'static str = "entity_action_state";
+
+	fn get_name_id(self) -> &'static str {
 		match self {
 			Self::Idle => "idle",
 			Self::Walking => "walking",
+			Self::Fishing => "fishing",
 		}
 	}
-
-	pub fn get_name_map() -> HashMap<String, Self> {
-		let mut out = HashMap::new();
-		for tile in Self::iter() {
-			out.insert(tile.get_name_id().to_string(), tile);
-		}
-		out
-	}
 }
\ No newline at end of file
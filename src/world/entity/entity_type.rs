@@ -1,15 +1,11 @@
-use std::collections::HashMap;
+use crate::{render::texture::Texture, world::item::inventory::Inventory, io::{namespace::Namespace, file_reader::FileReader, file_writer::FileWriter, record::Record, serializable::Serializable, registry::Registered}, error::Error};
 
-use crate::{render::texture::Texture, world::{item::inventory::Inventory, difficulty::Difficulty}, io::{namespace::Namespace, file_reader::FileReader, file_writer::FileWriter}, error::Error};
-
-use strum::IntoEnumIterator;
 use strum_macros::{EnumDiscriminants, EnumCount, EnumIter};
 
 #[derive(Clone, EnumDiscriminants)]
 #[strum_discriminants(name(EntityVariant), derive(EnumCount, EnumIter))]
-#[repr(u8)]
 pub enum EntityType {
-	Player { inventory: Inventory<50>, selected_item: u8, respawn_pos: [i64; 2], is_swaping_item: bool },
+	Player { inventory: Inventory<50>, selected_item: u8, respawn_pos: [i64; 2], is_swaping_item: bool, is_boating: bool, is_sprinting: bool, stamina: u16 },
 }
 
 impl EntityType {
@@ -19,62 +15,86 @@ impl EntityType {
 		}
 	}
 
-	/// Save
+	/// Push the type of the entity and the fields for the type to the record of an entity.
 	pub fn serialize(&self, file: &mut FileWriter) {
 		// Push id
-		file.push_u8(EntityVariant::from(self) as u8);
+		file.push_field("type", |field| field.push_id(EntityVariant::from(self) as usize));
 		
 		match self {
-			Self::Player { inventory, selected_item, respawn_pos, is_swaping_item: _ } => {
-				// Push inventory
-				inventory.serialize(file);
-				// Push selected item
-				file.push_u8(*selected_item);
-				// Push respawn pos
-				file.push_world_pos(*respawn_pos);
+			Self::Player { inventory, selected_item, respawn_pos, is_swaping_item: _, is_boating, is_sprinting: _, stamina } => {
+				file.push_value_field("inventory", inventory);
+				file.push_value_field("selected_item", selected_item);
+				file.push_value_field("respawn_pos", respawn_pos);
+				file.push_value_field("is_boating", is_boating);
+				file.push_value_field("stamina", stamina);
 			},
 		}
 	}
 
-	/// Load an entity
-	pub fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32, _difficulty: Difficulty) -> Result<Self, Error> {
-		// Get variant
-		let variant = *namespace.entities.get(file.read_u8()? as usize).ok_or(Error::IDOutOfNamespaceBounds)?;
+	/// Check that the fields of an entity type that has been loaded are in range.
+	fn validate(self) -> Result<Self, Error> {
+		match &self {
+			Self::Player { inventory, selected_item, .. } if *selected_item as usize >= inventory.items.len() => Err(Error::InvalidFieldValue("selected_item")),
+			_ => Ok(self),
+		}
+	}
 
-		Ok(match variant {
-			EntityVariant::Player => {
-				let inventory = Inventory::deserialize(file, namespace, version)?;
-				let selected_item = file.read_u8()?;
-				// Respawn pos
-				let respawn_pos = match version {
-					0 => [0, 0],
-					_ => file.read_world_pos()?,
-				};
-				
-				Self::Player { inventory, selected_item, respawn_pos, is_swaping_item: false }
-			}
-		})
+	/// Load the type of an entity from the record of an entity.
+	pub fn deserialize_record(record: &Record, namespace: &Namespace, version: u32) -> Result<Self, Error> {
+		match record.read_required("type", |field| namespace.entities.read(field, version))? {
+			EntityVariant::Player => Self::Player {
+				inventory: record.read_value("inventory", namespace, version)?.unwrap_or_else(Inventory::new),
+				selected_item: record.read_value("selected_item", namespace, version)?.unwrap_or(0),
+				respawn_pos: record.read_value("respawn_pos", namespace, version)?.unwrap_or([0, 0]),
+				is_swaping_item: false,
+				is_boating: record.read_value("is_boating", namespace, version)?.unwrap_or(false),
+				is_sprinting: false,
+				stamina: record.read_value("stamina", namespace, version)?.unwrap_or(EntityVariant::Player.max_stamina()),
+			},
+		}.validate()
+	}
+
+	/// Load the type of an entity saved before version 9.
+	pub fn deserialize_v8(file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<Self, Error> {
+		match namespace.entities.read(file, version)? {
+			EntityVariant::Player => Self::Player {
+				inventory: Inventory::deserialize(file, namespace, version)?,
+				selected_item: u8::deserialize(file, namespace, version)?,
+				respawn_pos: <[i64; 2]>::deserialize_since(file, namespace, version, 1, || [0, 0])?,
+				is_swaping_item: false,
+				is_boating: bool::deserialize_since(file, namespace, version, 4, || false)?,
+				is_sprinting: false,
+				stamina: u16::deserialize_since(file, namespace, version, 6, || EntityVariant::Player.max_stamina())?,
+			},
+		}.validate()
 	}
 }
 
 impl EntityVariant {
-	pub const fn get_name_id(self) -> &'static str {
+	pub fn max_health(self) -> u32 {
 		match self {
-			Self::Player => "player",
+			Self::Player { .. } => 100,
 		}
 	}
 
-	pub fn get_name_map() -> HashMap<String, Self> {
-		let mut out = HashMap::new();
-		for tile in Self::iter() {
-			out.insert(tile.get_name_id().to_string(), tile);
+	pub fn max_stamina(self) -> u16 {
+		match self {
+			Self::Player { .. } => 600,
 		}
-		out
 	}
+}
 
-	pub fn max_health(self) -> u32 {
+impl Registered for EntityVariant {
+	const CATEGORY: &'static str = "entity";
+
+	fn get_name_id(self) -> &'static str {
 		match self {
-			Self::Player { .. } => 100,
+			Self::Player => "player",
 		}
 	}
+
+	/// Entities that are not known fail to load when they are used.
+	fn from_unknown_name(_name: &str) -> Result<Option<Self>, Error> {
+		Ok(None)
+	}
 }
\ No newline at end of file
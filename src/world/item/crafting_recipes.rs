@@ -1,4 +1,4 @@
-use crate::world::item::item_category::ItemCategory;
+use crate::world::{item::item_category::ItemCategory, tile::tile::Tile};
 use super::item::Item;
 
 macro_rules! recipe {
@@ -22,6 +22,10 @@ impl CraftingRecipes {
 			Self::Quick => [
 				recipe!([(ItemCategory::Single(Item::SharpendFlint), 1), (ItemCategory::Stick, 1)], [(Item::FlintAxe, 1)]),
 				recipe!([(ItemCategory::Single(Item::SharpendFlint), 5), (ItemCategory::Stick, 1)], [(Item::FlintShovel, 1)]),
+				recipe!([(ItemCategory::Stick, 3)], [(Item::FishingRod, 1)]),
+				recipe!([(ItemCategory::Stick, 8)], [(Item::Boat, 1)]),
+				recipe!([(ItemCategory::Stick, 6)], [(Item::Tile(Tile::Door(false)), 1)]),
+				recipe!([(ItemCategory::Stick, 4)], [(Item::Tile(Tile::Gate(false)), 1)]),
 			].as_slice(),
 		}
 	}
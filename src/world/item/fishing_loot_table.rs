@@ -0,0 +1,75 @@
+use std::ops::RangeInclusive;
+
+use rand::{thread_rng, Rng};
+
+use crate::world::tile::tile_stack::TileStack;
+
+use super::item::Item;
+
+/// How many ticks a fish will stay on the hook for before getting away.
+pub const FISH_BITE_WINDOW: u16 = 60;
+/// Water deeper than this world generation height counts as ocean.
+const OCEAN_HEIGHT: f64 = -0.4;
+
+macro_rules! loot {
+	( $loot:expr ) => {
+		{
+			const A: &[(Item, u16)] = $loot.as_slice();
+			A
+		}
+	};
+}
+
+/// What can be caught when fishing in a body of water.
+#[derive(Copy, Clone)]
+pub enum FishingLootTable {
+	Lake,
+	Ocean,
+}
+
+impl FishingLootTable {
+	/// Get the loot table for the water at a world pos.
+	pub fn at_pos(pos: [i64; 2], world_seed: u32) -> Self {
+		match TileStack::get_generation_height(pos, world_seed) < OCEAN_HEIGHT {
+			true => Self::Ocean,
+			false => Self::Lake,
+		}
+	}
+
+	/// Get the items that can be caught along with their weights.
+	pub const fn get_loot(self, is_night: bool) -> &'static [(Item, u16)] {
+		match (self, is_night) {
+			(Self::Lake, false) => loot!([(Item::RawTrout, 70), (Item::Seaweed, 15), (Item::OldBoot, 15)]),
+			(Self::Lake, true) => loot!([(Item::RawTrout, 30), (Item::Catfish, 40), (Item::Seaweed, 15), (Item::OldBoot, 15)]),
+			(Self::Ocean, false) => loot!([(Item::RawCod, 60), (Item::Seaweed, 30), (Item::OldBoot, 10)]),
+			(Self::Ocean, true) => loot!([(Item::RawCod, 45), (Item::Seaweed, 45), (Item::OldBoot, 10)]),
+		}
+	}
+
+	/// How many ticks it takes for a fish to bite after casting.
+	pub const fn get_bite_wait_range(self) -> RangeInclusive<u16> {
+		match self {
+			Self::Lake => 300..=900,
+			Self::Ocean => 200..=600,
+		}
+	}
+
+	/// Get how long to wait for a bite after casting plus the time the fish stays on the hook.
+	pub fn roll_bite_wait(self) -> u16 {
+		thread_rng().gen_range(self.get_bite_wait_range()) + FISH_BITE_WINDOW
+	}
+
+	/// Get a random item from the loot table.
+	pub fn roll(self, is_night: bool) -> Item {
+		let loot = self.get_loot(is_night);
+		let total_weight: u16 = loot.iter().map(|(_, weight)| weight).sum();
+		let mut roll = thread_rng().gen_range(0..total_weight);
+		for (item, weight) in loot {
+			if roll < *weight {
+				return item.clone();
+			}
+			roll -= weight;
+		}
+		Item::None
+	}
+}
\ No newline at end of file
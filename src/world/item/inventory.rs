@@ -1,4 +1,4 @@
-use crate::{io::{file_writer::FileWriter, file_reader::FileReader, namespace::Namespace}, error::Error};
+use crate::{io::{file_writer::FileWriter, file_reader::FileReader, namespace::Namespace, serializable::Serializable}, error::Error};
 
 use super::{item::Item, item_category::ItemCategory};
 
@@ -105,24 +105,26 @@ impl<const SLOT_COUNT: usize> Inventory<SLOT_COUNT> {
 		// Success
 		true
 	}
+}
 
-	pub fn serialize(&self, file: &mut FileWriter) {
+/// An inventory is saved as the item and amount of each of its slots.
+impl<const SLOT_COUNT: usize> Serializable for Inventory<SLOT_COUNT> {
+	fn serialize(&self, file: &mut FileWriter) {
 		// Serialize each item stack and its stack amount.
 		for (stack_item, stack_amount) in self.items.iter() {
 			stack_item.serialize(file);
-			file.push_u8(*stack_amount);
+			stack_amount.serialize(file);
 		}
 	}
 
-	pub fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<Self, Error> {
+	fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<Self, Error> {
 		// Create blank.
 		let mut inventory = Self::new();
 		// Read the item type and id for each slot.
 		for (stack_item, stack_amount) in inventory.items.iter_mut() {
 			*stack_item = Item::deserialize(file, namespace, version)?;
-			*stack_amount = file.read_u8()?;
+			*stack_amount = u8::deserialize(file, namespace, version)?;
 		}
-		
 		Ok(inventory)
 	}
 }
\ No newline at end of file
@@ -1,7 +1,4 @@
-use std::collections::HashMap;
-
-use crate::{world::{tile::{tile::Tile, tile_stack::TileStack}, chunk::chunk_pool_offset::ChunkPoolOffset}, render::texture::Texture, io::{namespace::Namespace, file_reader::FileReader, file_writer::FileWriter}, error::Error};
-use strum::IntoEnumIterator;
+use crate::{world::{tile::{tile::Tile, tile_stack::TileStack}, chunk::chunk_pool_offset::ChunkPoolOffset, direction::Direction4}, render::texture::Texture, io::{namespace::Namespace, file_reader::FileReader, file_writer::FileWriter, serializable::Serializable, registry::Registered}, error::Error};
 
 use strum_macros::{EnumDiscriminants, EnumCount, EnumIter};
 
@@ -10,7 +7,6 @@ use super::item_drop::ItemDrop;
 /// An item that can exist in a player's inventory.
 #[derive(Clone, EnumDiscriminants, PartialEq, Eq)]
 #[strum_discriminants(name(ItemVariant), derive(EnumCount, EnumIter))]
-#[repr(u8)]
 pub enum Item {
 	None,
 	Hammer,
@@ -28,6 +24,14 @@ pub enum Item {
 	FlintAxe,
 	Acorn,
 	PineCone,
+	FishingRod,
+	RawTrout,
+	RawCod,
+	Catfish,
+	Seaweed,
+	OldBoot,
+	Boat,
+	Unknown(String, Vec<u8>), // An item that this version of the game does not know about, the name and data are saved so a version of the game that knows about the item can load it.
 }
 
 impl Item {
@@ -50,6 +54,14 @@ impl Item {
 			Self::FlintShovel => Texture::FlintShovel,
 			Self::Acorn => Texture::Acorn,
 			Self::PineCone => Texture::PineCone,
+			Self::FishingRod => Texture::FishingRod,
+			Self::RawTrout => Texture::RawTrout,
+			Self::RawCod => Texture::RawCod,
+			Self::Catfish => Texture::Catfish,
+			Self::Seaweed => Texture::Seaweed,
+			Self::OldBoot => Texture::OldBoot,
+			Self::Boat => Texture::Boat,
+			Self::Unknown(..) => Texture::NoTexture,
 		}
 	}
 
@@ -58,7 +70,7 @@ impl Item {
 	}
 
 	/// The item is used, returns weather the item should be consumed and the drops to be added to the player inventory.
-	pub fn use_stack_mut_self(self_stack: &mut (Self, u8), chunk_pool_used_on: &mut ChunkPoolOffset) -> (bool, Vec<ItemDrop>) {
+	pub fn use_stack_mut_self(self_stack: &mut (Self, u8), chunk_pool_used_on: &mut ChunkPoolOffset, facing: Direction4) -> (bool, Vec<ItemDrop>) {
 		let (item, _count) = self_stack;
 		match item {
 			// Tools and nothing
@@ -77,7 +89,7 @@ impl Item {
 					Some(tile) => tile,
 					None => return (false, Vec::new()),
 				};
-				tile_stack.needs_redrawing = true;
+				tile_stack.mark_modified();
 				(false, tile.get_drops())
 			}
 			// Place a tile
@@ -89,8 +101,13 @@ impl Item {
 				if !tile.can_place_on(tile_stack) {
 					return (false, Vec::new());
 				}
-				tile_stack.tiles.push(tile.clone());
-				tile_stack.needs_redrawing = true;
+				// One way paths point in the direction the user is facing.
+				let mut tile = tile.clone();
+				if let Tile::OneWayPath(direction) = &mut tile {
+					*direction = facing;
+				}
+				tile_stack.tiles.push(tile);
+				tile_stack.mark_modified();
 				(true, Vec::new())
 			}
 			// Place item
@@ -104,7 +121,7 @@ impl Item {
 					return (false, Vec::new())
 				}
 				tile_stack.tiles.push(to_place);
-				tile_stack.needs_redrawing = true;
+				tile_stack.mark_modified();
 				(true, Vec::new())
 			}
 			// Sharpen flint rock or place it.
@@ -125,7 +142,7 @@ impl Item {
 							return (false, Vec::new())
 						}
 						tile_stack.tiles.push(to_place);
-						tile_stack.needs_redrawing = true;
+						tile_stack.mark_modified();
 						(true, Vec::new())
 					}
 				}
@@ -140,7 +157,7 @@ impl Item {
 					return (false, Vec::new());
 				}
 				tile_stack.tiles.push(tile);
-				tile_stack.needs_redrawing = true;
+				tile_stack.mark_modified();
 				return (true, Vec::new());
 			}
 			Self::PineCone => {
@@ -153,9 +170,27 @@ impl Item {
 					return (false, Vec::new());
 				}
 				tile_stack.tiles.push(tile);
-				tile_stack.needs_redrawing = true;
+				tile_stack.mark_modified();
 				return (true, Vec::new());
 			}
+			// Put a boat on water.
+			Self::Boat => {
+				let tile_stack = match chunk_pool_used_on.get_origin_tile_stack_mut() {
+					Some(tile_stack) => tile_stack,
+					None => return (false, Vec::new()),
+				};
+				let tile = Tile::Boat;
+				if !tile.can_place_on(tile_stack) {
+					return (false, Vec::new());
+				}
+				tile_stack.tiles.push(tile);
+				tile_stack.mark_modified();
+				(true, Vec::new())
+			}
+			// Casting the fishing rod is handled by the player tick.
+			Self::FishingRod | Self::RawTrout | Self::RawCod | Self::Catfish | Self::Seaweed | Self::OldBoot => (false, Vec::new()),
+			// Items that are not known cannot be used.
+			Self::Unknown(..) => (false, Vec::new()),
 			//_ => false,
 		}
 	}
@@ -163,6 +198,10 @@ impl Item {
 	pub fn can_break(&self, tile_stack: &TileStack) -> bool {
 		match self {
 			Self::SandboxDestroyWand => true,
+			Self::Hammer | Self::FlintHammer => match tile_stack.tiles.last() {
+				Some(top_tile) => top_tile.is_smashable(),
+				None => false,
+			}
 			Self::Shovel | Self::FlintShovel => tile_stack.tiles.len() == 1,
 			Self::Axe | Self::FlintAxe => match tile_stack.tiles.last() {
 				Some(top_tile) => top_tile.is_choppable(),
@@ -182,23 +221,96 @@ impl Item {
 			*self = Item::None;
 		}
 	}
+}
+
+impl Registered for ItemVariant {
+	const CATEGORY: &'static str = "item";
+
+	fn get_name_id(self) -> &'static str {
+		match self {
+			Self::Axe => "axe",
+			Self::Tile => "tile",
+			Self::Hammer => "hammer",
+			Self::None => "none",
+			Self::SandboxDestroyWand => "sandbox_destroy_wand",
+			Self::Shovel => "shovel",
+			Self::Rock => "rock",
+			Self::FlintRock => "flint_rock",
+			Self::PineStick => "pine_stick",
+			Self::OakStick => "oak_stick",
+			Self::SharpendFlint => "sharpend_flint",
+			Self::FlintAxe => "flint_axe",
+			Self::FlintShovel => "flint_shovel",
+			Self::FlintHammer => "flint_hammer",
+			Self::Acorn => "acorn",
+			Self::PineCone => "pine_cone",
+			Self::FishingRod => "fishing_rod",
+			Self::RawTrout => "raw_trout",
+			Self::RawCod => "raw_cod",
+			Self::Catfish => "catfish",
+			Self::Seaweed => "seaweed",
+			Self::OldBoot => "old_boot",
+			Self::Boat => "boat",
+			Self::Unknown => "unknown",
+		}
+	}
+
+	/// Items that are not known are loaded as unknown items that keep their name and data.
+	fn from_unknown_name(_name: &str) -> Result<Option<Self>, Error> {
+		Ok(Some(Self::Unknown))
+	}
+}
 
+impl Serializable for Item {
 	/// Save
-	pub fn serialize(&self, file: &mut FileWriter) {
+	/// The data of the item is pushed after its length so that items that are not known when loading can be skipped over and kept.
+	fn serialize(&self, file: &mut FileWriter) {
 		// Push id
-		file.push_u8(ItemVariant::from(self) as u8);
-		
+		file.push_id(ItemVariant::from(self) as usize);
+		// Push data
+		let mut item_data = FileWriter::new();
 		match self {
-			Self::Tile(tile) => tile.serialize(file),
+			Self::Tile(tile) => tile.serialize(&mut item_data),
+			Self::Unknown(name, data) => {
+				item_data.push_string(name);
+				item_data.data.extend(data);
+			}
 			_ => {}
 		}
+		file.push_u16(item_data.data.len() as u16);
+		file.data.extend(item_data.data);
 	}
 
 	/// Create a item from disk data.
-	pub fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<Self, Error> {
-		let variant = *namespace.items.get(file.read_u8()? as usize).ok_or(Error::IDOutOfNamespaceBounds)?;
+	fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<Self, Error> {
+		let item_id = file.read_id(version)?;
+		let variant = namespace.items.get(item_id)?;
+		// Get where the data of the item ends, items saved before version 8 do not have the length of their data saved
+		let item_data_end = match version {
+			0..=7 => None,
+			_ => {
+				let length = file.read_u16()? as usize;
+				Some(file.get_data_end(length)?)
+			}
+		};
+		// Items that were not known when saved have their name saved with their data, other unknown items get their name from the namespace.
+		// Items saved with their name that this version of the game knows about are loaded as the known item.
+		let (variant, unknown_name) = match variant {
+			ItemVariant::Unknown => {
+				let name = namespace.items.get_name(item_id)?;
+				let name = match name == ItemVariant::Unknown.get_name_id() {
+					true => file.read_string()?,
+					false => name.clone(),
+				};
+				match ItemVariant::get_name_map().get(&name) {
+					Some(known_variant) if *known_variant != ItemVariant::Unknown => (*known_variant, None),
+					_ => (ItemVariant::Unknown, Some(name)),
+				}
+			}
+			_ => (variant, None),
+		};
 
-		Ok(match variant {
+		let item = match variant {
 			ItemVariant::Axe => Self::Axe,
 			ItemVariant::Hammer => Self::Hammer,
 			ItemVariant::None => Self::None,
@@ -215,37 +327,28 @@ impl Item {
 			ItemVariant::FlintHammer => Self::FlintHammer,
 			ItemVariant::Acorn => Self::Acorn,
 			ItemVariant::PineCone => Self::PineCone,
-		})
-	}
-}
-
-impl ItemVariant {
-	pub const fn get_name_id(self) -> &'static str {
-		match self {
-			Self::Axe => "axe",
-			Self::Tile => "tile",
-			Self::Hammer => "hammer",
-			Self::None => "none",
-			Self::SandboxDestroyWand => "sandbox_destroy_wand",
-			Self::Shovel => "shovel",
-			Self::Rock => "rock",
-			Self::FlintRock => "flint_rock",
-			Self::PineStick => "pine_stick",
-			Self::OakStick => "oak_stick",
-			Self::SharpendFlint => "sharpend_flint",
-			Self::FlintAxe => "flint_axe",
-			Self::FlintShovel => "flint_shovel",
-			Self::FlintHammer => "flint_hammer",
-			Self::Acorn => "acorn",
-			Self::PineCone => "pine_cone"
-		}
-	}
-
-	pub fn get_name_map() -> HashMap<String, Self> {
-		let mut out = HashMap::new();
-		for tile in Self::iter() {
-			out.insert(tile.get_name_id().to_string(), tile);
+			ItemVariant::FishingRod => Self::FishingRod,
+			ItemVariant::RawTrout => Self::RawTrout,
+			ItemVariant::RawCod => Self::RawCod,
+			ItemVariant::Catfish => Self::Catfish,
+			ItemVariant::Seaweed => Self::Seaweed,
+			ItemVariant::OldBoot => Self::OldBoot,
+			ItemVariant::Boat => Self::Boat,
+			ItemVariant::Unknown => {
+				let name = unknown_name.expect("Constructed from Some() if the variant is unknown.");
+				// The length of the data of unknown items saved before version 8 can not be known
+				let item_data_end = item_data_end.ok_or_else(|| Error::UnknownName(ItemVariant::CATEGORY, name.clone()))?;
+				let data_length = item_data_end.checked_sub(file.read_index).ok_or(Error::InvalidDataLength)?;
+				Self::Unknown(name, file.read_bytes(data_length)?)
+			}
+		};
+		// Skip over data that this version of the game does not know about
+		if let Some(item_data_end) = item_data_end {
+			if file.read_index > item_data_end {
+				return Err(Error::InvalidDataLength);
+			}
+			file.read_index = item_data_end;
 		}
-		out
+		Ok(item)
 	}
 }
\ No newline at end of file
@@ -3,3 +3,4 @@ pub mod item_drop;
 pub mod inventory;
 pub mod crafting_recipes;
 pub mod item_category;
+pub mod fishing_loot_table;
\ No newline at end of file
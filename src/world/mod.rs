@@ -5,3 +5,5 @@ pub mod tile;
 pub mod direction;
 pub mod item;
 pub mod difficulty;
+pub mod world_upgrade;
+pub mod world_overview;
\ No newline at end of file
@@ -1,15 +1,11 @@
-use std::collections::HashMap;
-
 use strum_macros::{EnumDiscriminants, EnumCount, EnumIter};
-use strum::{IntoEnumIterator};
 
-use crate::{render::{vertex::Vertex, texture::Texture}, world::{entity::entity::Entity, item::{item::Item, item_drop::ItemDrop}}, io::{namespace::Namespace, file_reader::FileReader, file_writer::FileWriter}, error::Error};
+use crate::{render::{vertex::Vertex, texture::Texture}, world::{entity::entity::Entity, item::{item::Item, item_drop::ItemDrop}, direction::Direction4}, io::{namespace::Namespace, file_reader::FileReader, file_writer::FileWriter, serializable::Serializable, registry::Registered}, error::Error};
 
 use super::{tile_movement_type::TileMovementType, tile_stack::TileStack};
 
 #[derive(Clone, EnumDiscriminants, PartialEq, Eq)]
 #[strum_discriminants(name(TileVariant), derive(EnumCount, EnumIter))]
-#[repr(u8)]
 pub enum Tile {
 	None,
 	Grass,
@@ -25,6 +21,12 @@ pub enum Tile {
 	Sand,
 	DroppedItemStack(Box<Item>, u16),
 	Item(Box<Item>),
+	Boat,
+	Door(bool), // Is open.
+	Gate(bool), // Is open.
+	OneWayPath(Direction4), // The direction that entities can move onto the tile in.
+	ShallowWater,
+	Unknown(String, Vec<u8>), // A tile that this version of the game does not know about, the name and data are saved so a version of the game that knows about the tile can load it.
 }
 
 /// A tile in the world
@@ -46,13 +48,26 @@ impl Tile {
 			Self::Path => Texture::Path,
 			Self::DroppedItemStack(..) => Texture::DroppedItems,
 			Self::Item(item) => item.get_texture(),
+			Self::Boat => Texture::Boat,
+			Self::Door(false) => Texture::DoorClosed,
+			Self::Door(true) => Texture::DoorOpen,
+			Self::Gate(false) => Texture::GateClosed,
+			Self::Gate(true) => Texture::GateOpen,
+			Self::OneWayPath(direction) => match direction {
+				Direction4::North => Texture::OneWayPathNorth,
+				Direction4::East => Texture::OneWayPathEast,
+				Direction4::South => Texture::OneWayPathSouth,
+				Direction4::West => Texture::OneWayPathWest,
+			},
+			Self::ShallowWater => Texture::ShallowWater,
+			Self::Unknown(..) => Texture::NoTexture,
 		}
 	}
 
 	/// Renders the tile to a tri list.
 	pub fn render(&self, pos: [i64; 2], vertices_in_out: &mut Vec<Vertex>) {
 		match self {
-			_ => vertices_in_out.extend(self.get_texture().render_basic(pos, [0, 0])),
+			_ => vertices_in_out.extend(self.get_texture().render_basic(pos, [0., 0.])),
 		}
 	}
 
@@ -61,7 +76,7 @@ impl Tile {
 		match self {
 			Self::None => TileMovementType::Wall,
 			Self::Grass => TileMovementType::Clear,
-			Self::Water => TileMovementType::Wall,
+			Self::Water => TileMovementType::BoatOnly,
 			Self::Sand => TileMovementType::Clear,
 			Self::PineTree => TileMovementType::Wall,
 			Self::OakTree => TileMovementType::Wall,
@@ -73,65 +88,49 @@ impl Tile {
 			Self::Path => TileMovementType::Clear,
 			Self::DroppedItemStack(..) => TileMovementType::Clear,
 			Self::Item(..) => TileMovementType::Clear,
+			Self::Boat => TileMovementType::WalkingOnly,
+			Self::Door(is_open) | Self::Gate(is_open) => match is_open {
+				true => TileMovementType::Clear,
+				false => TileMovementType::Wall,
+			},
+			Self::OneWayPath(direction) => TileMovementType::OneWay(*direction),
+			Self::ShallowWater => TileMovementType::Clear,
+			Self::Unknown(..) => TileMovementType::Wall,
 		}
 	}
 
-	/// Called when an entity trys to move to this tile and returns weather or not the entity can move to this tile.
-	pub fn entity_try_move_to(&mut self, _entity: &mut Entity) -> bool {
-		let out = match self.get_tile_movement_type() {
-			TileMovementType::Clear => true,
-			TileMovementType::Wall => false,
-		};
-		out
+	/// How much walk progress an entity makes each tick when moving onto the tile, 16 is normal walking speed.
+	pub const fn get_walk_speed(&self) -> u16 {
+		match self {
+			Self::Path | Self::OneWayPath(..) => 20,
+			Self::Sand | Self::BlackSand | Self::Gravel | Self::Water => 12,
+			Self::ShallowWater => 8,
+			_ => 16,
+		}
 	}
 
-	/// Get data for the tile to save to disk.
-	pub fn serialize(&self, file: &mut FileWriter) {
-		file.push_u8(TileVariant::from(self) as u8);
+	/// Called when an entity interacts with the tile, returns weather the tile did anything.
+	pub fn interact(&mut self) -> bool {
 		match self {
-			Self::DroppedItemStack(item, amount) => {
-				item.serialize(file);
-				file.push_u16(*amount);
+			Self::Door(is_open) | Self::Gate(is_open) => {
+				*is_open = !*is_open;
+				true
 			}
-			Self::Item(item) => item.serialize(file),
-			_ => {},
+			_ => false,
 		}
 	}
 
-	/// Create a tile form disk data.
-	pub fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<Self, Error> {
-		let variant = *namespace.tiles.get(file.read_u8()? as usize).ok_or(Error::IDOutOfNamespaceBounds)?;
-		Ok(match variant {
-			TileVariant::None => panic!("None tile should not exist."),
-			TileVariant::Grass => Self::Grass,
-			TileVariant::Water => Self::Water,
-			TileVariant::Sand => Self::Sand,
-			TileVariant::PineTree => Self::PineTree,
-			TileVariant::OakTree => Self::OakTree,
-			TileVariant::Flowers => Self::Flowers,
-			TileVariant::FlowersRedYellow => Self::FlowersRedYellow,
-			TileVariant::Rocks => Self::Rocks,
-			TileVariant::Gravel => Self::Gravel,
-			TileVariant::BlackSand => Self::BlackSand,
-			TileVariant::Path => Self::Path,
-			TileVariant::DroppedItemStack => {
-				let item = Item::deserialize(file, namespace, version)?;
-				let amount = file.read_u16()?;
-				Self::DroppedItemStack(Box::new(item), amount)
-			}
-			TileVariant::Item => {
-				let item = Item::deserialize(file, namespace, version)?;
-				Self::Item(Box::new(item))
-			}
-		})
+	/// Called when an entity trys to move to this tile and returns weather or not the entity can move to this tile.
+	pub fn entity_try_move_to(&mut self, entity: &mut Entity, direction: Direction4) -> bool {
+		self.get_tile_movement_type().can_entity_move(entity, direction)
 	}
 
 	/// Create a tile form disk data.
 	pub fn deserialize_v0(data: &[u8], namespace: &Namespace, _version: u32) -> Option<(Self, usize)> {
 		let tile_id = *data.get(0)? as usize;
-		let tile_variant = *namespace.tiles.get(tile_id)?;
+		let tile_variant = namespace.tiles.get(tile_id).ok()?;
 		Some((match tile_variant {
-			TileVariant::None => panic!("None tile should not exist."),//Self::None,
+			TileVariant::None => return None,
 			TileVariant::Grass => Self::Grass,
 			TileVariant::Water => Self::Water,
 			TileVariant::Sand => Self::Sand,
@@ -143,7 +142,8 @@ impl Tile {
 			TileVariant::Gravel => Self::Gravel,
 			TileVariant::BlackSand => Self::BlackSand,
 			TileVariant::Path => Self::Path,
-			_ => panic!("Tile should not exist in this version."),
+			TileVariant::Unknown => return Some((Self::Unknown(namespace.tiles.get_name(tile_id).ok()?.clone(), data.get(1..)?.to_vec()), data.len())),
+			_ => return None,
 		}, 1))
 	}
 
@@ -155,6 +155,11 @@ impl Tile {
 		}
 	}
 
+	/// Can a hammer be used on the tile?
+	pub fn is_smashable(&self) -> bool {
+		matches!(self, Self::Door(..) | Self::Gate(..) | Self::OneWayPath(..))
+	}
+
 	/// Can water be placed on top?
 	pub fn is_floodable(&self) -> bool {
 		match self {
@@ -174,7 +179,7 @@ impl Tile {
 	/// Can the tile be broken with the fist?
 	pub fn is_pluckable(&self) -> bool {
 		match self {
-			Self::Flowers | Self::FlowersRedYellow | Self::Item(..) => true,
+			Self::Flowers | Self::FlowersRedYellow | Self::Item(..) | Self::Boat => true,
 			_ => false,
 		}
 	}
@@ -192,7 +197,7 @@ impl Tile {
 		match self {
 			Self::None => panic!("None tile should not exist."),
 			Self::Grass | Self::Gravel | Self::Sand | Self::BlackSand => tile_stack.tiles.is_empty(),
-			Self::Water => match tile_stack.tiles.last() {
+			Self::Water | Self::ShallowWater => match tile_stack.tiles.last() {
 				Some(top_tile) => top_tile.is_floodable(),
 				None => false,
 			}
@@ -202,14 +207,16 @@ impl Tile {
 			}
 			Self::Rocks => match tile_stack.tiles.last() {
 				Some(top_tile) => match top_tile {
-					Self::OakTree | Self::PineTree | Self::Flowers | Self::FlowersRedYellow | Self::Path | Self::Rocks | Self::DroppedItemStack(..) => false,
+					Self::OakTree | Self::PineTree | Self::Flowers | Self::FlowersRedYellow | Self::Path | Self::Rocks | Self::DroppedItemStack(..) | Self::Boat |
+					Self::Door(..) | Self::Gate(..) | Self::OneWayPath(..) => false,
 					_ => true,
 				},
 				None => false,
 			}
 			Self::Path => match tile_stack.tiles.last() {
 				Some(top_tile) => match top_tile {
-					Self::OakTree | Self::PineTree | Self::Flowers | Self::FlowersRedYellow | Self::Water | Self::Path | Self::Rocks => false,
+					Self::OakTree | Self::PineTree | Self::Flowers | Self::FlowersRedYellow | Self::Water | Self::ShallowWater | Self::Path | Self::Rocks | Self::Boat |
+					Self::Door(..) | Self::Gate(..) | Self::OneWayPath(..) => false,
 					_ => true,
 				},
 				None => false,
@@ -222,6 +229,12 @@ impl Tile {
 				};
 				top_tile.is_plain()
 			}
+			Self::Boat => tile_stack.tiles.last() == Some(&Self::Water),
+			Self::Door(..) | Self::Gate(..) | Self::OneWayPath(..) => match tile_stack.tiles.last() {
+				Some(top_tile) => top_tile.is_plain() || *top_tile == Self::Path,
+				None => false,
+			}
+			Self::Unknown(..) => false,
 		}
 	}
 
@@ -229,6 +242,10 @@ impl Tile {
 	pub fn get_drops(&self) -> Vec<ItemDrop> {
 		match self {
 			Self::Item(item) => vec![ItemDrop::Single(*item.clone())],
+			Self::Boat => vec![ItemDrop::Single(Item::Boat)],
+			Self::Door(..) => vec![ItemDrop::Single(Item::Tile(Self::Door(false)))],
+			Self::Gate(..) => vec![ItemDrop::Single(Item::Tile(Self::Gate(false)))],
+			Self::OneWayPath(..) => vec![ItemDrop::Single(Item::Tile(Self::OneWayPath(Direction4::North)))],
 			Self::OakTree => vec![
 				ItemDrop::RangedRandomAmount { item: Item::Acorn, amount_range: 1..=4 },
 				ItemDrop::RangedRandomAmount { item: Item::OakStick, amount_range: 0..=5 },
@@ -242,8 +259,10 @@ impl Tile {
 	}
 }
 
-impl TileVariant {
-	pub const fn get_name_id(self) -> &'static str {
+impl Registered for TileVariant {
+	const CATEGORY: &'static str = "tile";
+
+	fn get_name_id(self) -> &'static str {
 		match self {
 			Self::None => "none",
 			Self::Grass => "grass",
@@ -259,14 +278,118 @@ impl TileVariant {
 			Self::Path => "path",
 			Self::DroppedItemStack => "dropped_item_stack",
 			Self::Item => "item",
+			Self::Boat => "boat",
+			Self::Door => "door",
+			Self::Gate => "gate",
+			Self::OneWayPath => "one_way_path",
+			Self::ShallowWater => "shallow_water",
+			Self::Unknown => "unknown",
 		}
 	}
 
-	pub fn get_name_map() -> HashMap<String, Self> {
-		let mut out = HashMap::new();
-		for tile in Self::iter() {
-			out.insert(tile.get_name_id().to_string(), tile);
+	/// Tiles that are not known are loaded as unknown tiles that keep their name and data.
+	fn from_unknown_name(_name: &str) -> Result<Option<Self>, Error> {
+		Ok(Some(Self::Unknown))
+	}
+}
+
+impl Serializable for Tile {
+	/// Get data for the tile to save to disk.
+	/// The data of the tile is pushed after its length so that tiles that are not known when loading can be skipped over and kept.
+	fn serialize(&self, file: &mut FileWriter) {
+		file.push_id(TileVariant::from(self) as usize);
+		let mut tile_data = FileWriter::new();
+		match self {
+			Self::DroppedItemStack(item, amount) => {
+				item.serialize(&mut tile_data);
+				amount.serialize(&mut tile_data);
+			}
+			Self::Item(item) => item.serialize(&mut tile_data),
+			Self::Door(is_open) | Self::Gate(is_open) => is_open.serialize(&mut tile_data),
+			Self::OneWayPath(direction) => direction.serialize(&mut tile_data),
+			Self::Unknown(name, data) => {
+				tile_data.push_string(name);
+				tile_data.data.extend(data);
+			}
+			_ => {},
+		}
+		file.push_u16(tile_data.data.len() as u16);
+		file.data.extend(tile_data.data);
+	}
+
+	/// Create a tile form disk data.
+	fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<Self, Error> {
+		let tile_id = file.read_id(version)?;
+		let variant = namespace.tiles.get(tile_id)?;
+		// Get where the data of the tile ends, tiles saved before version 8 do not have the length of their data saved
+		let tile_data_end = match version {
+			0..=7 => None,
+			_ => {
+				let length = file.read_u16()? as usize;
+				Some(file.get_data_end(length)?)
+			}
+		};
+		// Tiles that were not known when saved have their name saved with their data, other unknown tiles get their name from the namespace.
+		// Tiles saved with their name that this version of the game knows about are loaded as the known tile.
+		let (variant, unknown_name) = match variant {
+			TileVariant::Unknown => {
+				let name = namespace.tiles.get_name(tile_id)?;
+				let name = match name == TileVariant::Unknown.get_name_id() {
+					true => file.read_string()?,
+					false => name.clone(),
+				};
+				match TileVariant::get_name_map().get(&name) {
+					Some(known_variant) if *known_variant != TileVariant::Unknown => (*known_variant, None),
+					_ => (TileVariant::Unknown, Some(name)),
+				}
+			}
+			_ => (variant, None),
+		};
+		// Tiles can hold items that hold tiles
+		file.enter_nested()?;
+		let tile = match variant {
+			TileVariant::None => return Err(Error::InvalidTile),
+			TileVariant::Grass => Self::Grass,
+			TileVariant::Water => Self::Water,
+			TileVariant::Sand => Self::Sand,
+			TileVariant::PineTree => Self::PineTree,
+			TileVariant::OakTree => Self::OakTree,
+			TileVariant::Flowers => Self::Flowers,
+			TileVariant::FlowersRedYellow => Self::FlowersRedYellow,
+			TileVariant::Rocks => Self::Rocks,
+			TileVariant::Gravel => Self::Gravel,
+			TileVariant::BlackSand => Self::BlackSand,
+			TileVariant::Path => Self::Path,
+			TileVariant::DroppedItemStack => {
+				let item = Item::deserialize(file, namespace, version)?;
+				let amount = u16::deserialize(file, namespace, version)?;
+				Self::DroppedItemStack(Box::new(item), amount)
+			}
+			TileVariant::Item => {
+				let item = Item::deserialize(file, namespace, version)?;
+				Self::Item(Box::new(item))
+			}
+			TileVariant::Boat => Self::Boat,
+			TileVariant::Door => Self::Door(bool::deserialize(file, namespace, version)?),
+			TileVariant::Gate => Self::Gate(bool::deserialize(file, namespace, version)?),
+			TileVariant::ShallowWater => Self::ShallowWater,
+			TileVariant::OneWayPath => Self::OneWayPath(Direction4::deserialize(file, namespace, version)?),
+			TileVariant::Unknown => {
+				let name = unknown_name.expect("Constructed from Some() if the variant is unknown.");
+				// The length of the data of unknown tiles saved before version 8 can not be known
+				let tile_data_end = tile_data_end.ok_or_else(|| Error::UnknownName(TileVariant::CATEGORY, name.clone()))?;
+				let data_length = tile_data_end.checked_sub(file.read_index).ok_or(Error::InvalidDataLength)?;
+				Self::Unknown(name, file.read_bytes(data_length)?)
+			}
+		};
+		file.exit_nested();
+		// Skip over data that this version of the game does not know about
+		if let Some(tile_data_end) = tile_data_end {
+			if file.read_index > tile_data_end {
+				return Err(Error::InvalidDataLength);
+			}
+			file.read_index = tile_data_end;
 		}
-		out
+		Ok(tile)
 	}
 }
\ No newline at end of file
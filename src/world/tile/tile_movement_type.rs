@@ -1,5 +1,23 @@
+use crate::world::{direction::Direction4, entity::entity::Entity};
+
 /// If and when an entity can move to a tile.
 pub enum TileMovementType {
 	Clear, // Can always move to tile.
 	Wall, // Can never move to tile.
+	OneWay(Direction4), // Can only move to tile when moving in the direction.
+	BoatOnly, // Can only move to tile when in a boat.
+	WalkingOnly, // Can only move to tile when not in a boat.
+}
+
+impl TileMovementType {
+	/// Get weather an entity moving in a direction can move to a tile with this movement type.
+	pub fn can_entity_move(&self, entity: &Entity, direction: Direction4) -> bool {
+		match self {
+			Self::Clear => true,
+			Self::Wall => false,
+			Self::OneWay(one_way_direction) => direction == *one_way_direction,
+			Self::BoatOnly => entity.is_boating(),
+			Self::WalkingOnly => !entity.is_boating(),
+		}
+	}
 }
\ No newline at end of file
@@ -1,14 +1,18 @@
 use noise::{Perlin, NoiseFn, Fbm};
 
-use crate::{render::{vertex::Vertex, texture::Texture}, world::{entity::{entity::Entity, entity_action_state::EntityActionState, entity_type::EntityType}, direction::Direction4, item::item::Item}, io::{namespace::Namespace, file_reader::FileReader, file_writer::FileWriter}, error::Error};
+use crate::{render::{vertex::Vertex, texture::Texture}, world::{entity::{entity::Entity, entity_action_state::EntityActionState, entity_type::EntityType}, direction::Direction4, item::item::Item}, io::{namespace::Namespace, file_reader::FileReader, file_writer::FileWriter, serializable::Serializable}, error::Error};
 
 use super::tile::{Tile, TileVariant};
 
+/// The version of the world generator that new worlds are created with, this is increased when the world generator changes how a seed generates.
+pub const GENERATOR_VERSION: u32 = 1;
+
 #[derive(Clone)]
 pub struct TileStack {
 	pub tiles: Vec<Tile>,
 	pub extra_vertices: Vec<Vertex>,
 	pub needs_redrawing: bool,
+	pub is_modified: bool, // If the tile stack has been changed since it was loaded or generated.
 }
 
 impl TileStack {
@@ -18,7 +22,7 @@ impl TileStack {
 			tile.render(pos, &mut vertices);
 		}
 		if self.tiles.len() == 0 {
-			vertices.extend(Texture::Pit.render_basic(pos, [0, 0]));
+			vertices.extend(Texture::Pit.render_basic(pos, [0., 0.]));
 		}
 		for x in 0..vertices.len().min(48) {
 			basic_vertices[x] = vertices[x];
@@ -41,15 +45,30 @@ impl TileStack {
 			tiles: Vec::new(),
 			needs_redrawing: true,
 			extra_vertices: Vec::new(),
+			is_modified: false,
 		}
 	}
 
+	/// Mark the tile stack as changed so that it is redrawn and the chunk it is in gets saved.
+	pub fn mark_modified(&mut self) {
+		self.needs_redrawing = true;
+		self.is_modified = true;
+	}
+
+	/// Get the height of the terrain at a pos used by the world generator, water is generated below -0.1.
+	pub fn get_generation_height(pos: [i64; 2], world_seed: u32) -> f64 {
+		Fbm::<Perlin>::new(world_seed).get([pos[0] as f64 / 64., pos[1] as f64 / 64.])
+	}
+
 	/// Generates tiles onto an empty tile stack.
-	pub fn generate(&mut self, pos: [i64; 2], world_seed: u32) {
-		let height = Fbm::<Perlin>::new(world_seed).get([pos[0] as f64 / 64., pos[1] as f64 / 64.]);
+	/// The generator version is the version that the world was created with so that worlds keep generating the same way after the world generator changes.
+	pub fn generate(&mut self, pos: [i64; 2], world_seed: u32, generator_version: u32) {
+		let height = Self::get_generation_height(pos, world_seed);
 		let sand_type = Fbm::<Perlin>::new(world_seed + 3).get([pos[0] as f64 / 64., pos[1] as f64 / 64.]);
 		self.tiles = match height {
-			_ if height < -0.1 => vec![Tile::Sand, Tile::Water],
+			// Shallow water at the shore was added in generator version 1
+			_ if height < -0.15 || (height < -0.1 && generator_version == 0) => vec![Tile::Sand, Tile::Water],
+			_ if height < -0.1 => vec![Tile::Sand, Tile::ShallowWater],
 			_ if height < 0.1 => match sand_type {
 				_ if sand_type > 0.4 => vec![Tile::BlackSand],
 				_ if sand_type < -0.4 => vec![Tile::Gravel],
@@ -92,7 +111,7 @@ impl TileStack {
 	pub fn entity_try_move_to(&mut self, entity: &mut Entity, direction: Direction4) {
 		let mut walk = false;
 		if let Some(top_tile) = self.tiles.last_mut() {
-			walk = top_tile.entity_try_move_to(entity);
+			walk = top_tile.entity_try_move_to(entity, direction);
 		}
 		if self.tiles.len() == 0 {
 			walk = true;
@@ -102,6 +121,26 @@ impl TileStack {
 		}
 	}
 
+	/// How much walk progress an entity makes each tick when moving onto or off of the tile stack.
+	pub fn get_walk_speed(&self) -> u16 {
+		match self.tiles.last() {
+			Some(top_tile) => top_tile.get_walk_speed(),
+			None => 16,
+		}
+	}
+
+	/// Called when an entity interacts with the tile stack, returns weather the top tile did anything.
+	pub fn interact(&mut self) -> bool {
+		let did_interact = match self.tiles.last_mut() {
+			Some(top_tile) => top_tile.interact(),
+			None => false,
+		};
+		if did_interact {
+			self.mark_modified();
+		}
+		did_interact
+	}
+
 	/// When an entity steps on the tile stack.
 	pub fn entity_move_to(&mut self, entity: &mut Entity) {
 		let inventory = match entity.entity_type {
@@ -115,10 +154,14 @@ impl TileStack {
 				_ => continue,
 			};
 			let leftover = inventory.add_items((*stack_item.clone(), *stack_amount)).1;
+			if leftover == *stack_amount {
+				continue;
+			}
 			*stack_amount = leftover;
 			if leftover == 0 {
 				self.tiles.remove(x);
 			}
+			self.mark_modified();
 		}
 	}
 
@@ -129,6 +172,7 @@ impl TileStack {
 		if item_to_add.is_none() {
 			return;
 		}
+		self.mark_modified();
 		// Add to exising dropped item stacks.
 		for tile in &mut self.tiles {
 			// Skip tiles that are not item stacks.
@@ -153,13 +197,6 @@ impl TileStack {
 		self.tiles.push(Tile::DroppedItemStack(Box::new(item_to_add), amount_left_to_add));
 	}
 
-	pub fn serialize(&self, file: &mut FileWriter) {
-		for tile in &self.tiles {
-			tile.serialize(file);
-		}
-		file.push_u8(TileVariant::None as u8);
-	}
-
 	pub fn load_v0(&mut self, tile_lengths: &[u8], tile_datas: &[u8], tile_lengths_index: &mut usize, tile_datas_index: &mut usize, namespace: &Namespace, version: u32) -> Option<()> {
 		loop {
 			let length = *tile_lengths.get(*tile_lengths_index)? as usize;
@@ -174,17 +211,28 @@ impl TileStack {
 		}
 		Some(())
 	}
+}
+
+/// A tile stack is saved as each of its tiles followed by a none tile ID.
+impl Serializable for TileStack {
+	fn serialize(&self, file: &mut FileWriter) {
+		for tile in &self.tiles {
+			tile.serialize(file);
+		}
+		file.push_id(TileVariant::None as usize);
+	}
 
-	pub fn deserialize(&mut self, file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<(), Error> {
+	fn deserialize(file: &mut FileReader, namespace: &Namespace, version: u32) -> Result<Self, Error> {
+		let mut tile_stack = Self::new();
 		loop {
-			let tile_id = *file.data.get(file.read_index).ok_or(Error::OutOfBoundsFileRead)?;
-			let variant = *namespace.tiles.get(tile_id as usize).ok_or(Error::IDOutOfNamespaceBounds)?;
-			if variant == TileVariant::None {
-				file.read_index += 1;
+			// Break if the next tile is a none tile, otherwise go back to the start of the tile
+			let tile_start = file.read_index;
+			if namespace.tiles.read(file, version)? == TileVariant::None {
 				break;
 			}
-			self.tiles.push(Tile::deserialize(file, namespace, version)?);
+			file.read_index = tile_start;
+			tile_stack.tiles.push(Tile::deserialize(file, namespace, version)?);
 		}
-		Ok(())
+		Ok(tile_stack)
 	}
 }
\ No newline at end of file
@@ -1,23 +1,46 @@
-use std::{fs::create_dir, path::PathBuf};
+use std::{path::{PathBuf, Path}, sync::Arc, task::{Context, Poll}};
 
-use crate::{render::{vertex::Vertex, render::world_pos_to_render_pos}, io::{io::IO, file_writer::FileWriter, file_reader::FileReader, namespace::Namespace}, gui::gui::GUI, validate_filename, error::Error};
+use futures::FutureExt;
+use noop_waker::noop_waker;
+use tokio::task::JoinHandle;
 
-use super::{chunk::chunk_pool::ChunkPool, entity::entity::Entity, difficulty::Difficulty};
+use crate::{render::{vertex::Vertex, render::world_pos_to_render_pos}, io::{io::{IO, SERIALIZATION_VERSION}, file_writer::FileWriter, file_reader::FileReader, namespace::Namespace, region_files::RegionFiles, storage::Storage, backups::create_backup, world_archive::{pack_world_folder, unpack_world_archive}}, gui::gui::GUI, validate_filename, error::Error};
+
+use super::{chunk::chunk_pool::{ChunkPool, ChunkPoolContext}, tile::tile_stack::GENERATOR_VERSION, entity::entity::Entity, difficulty::Difficulty, world_overview::WorldOverview};
+
+/// How many ticks a full day and night lasts.
+pub const TICKS_PER_DAY: u64 = 100 * 60 * 20;
+/// How many ticks a warning is shown in the HUD for.
+pub const WARNING_SHOW_TICKS: u16 = 100 * 10;
+/// How many ticks there are in a second.
+const TICKS_PER_SECOND: u64 = 100;
+/// How many ticks to wait before trying to save the player and world overview again after failing.
+const SAVE_RETRY_TICKS: u16 = 100;
 
 /// Contains everthing visable that isn't the GUI.
 pub struct World {
 	pub player: Option<Entity>,
 	chunk_pool: ChunkPool,
 	seed: u32,
+	generator_version: u32, // The version of the world generator that the world was created with.
+	world_id: u64, // Backups of the world are kept by it.
 	pub is_freeing: bool, // If true, the world is saving all chunks and preparing to be deleted from RAM.
 	pub is_freed: bool, // If true, the world is saved to disk and can be deleted from RAM.
 	pub name: String,
+	pub storage: Arc<dyn Storage>, // Where the files of the world are kept.
 	pub filepath: PathBuf, // Path to the world folder
 	pub chunks_filepath: PathBuf,
+	pub region_files: Arc<RegionFiles>,
 	pub namespaces_filepath: PathBuf,
 	pub overview_filepath: PathBuf,
 	pub player_filepath: PathBuf,
 	pub difficulty: Difficulty,
+	pub time: u64, // How many ticks the world has been played for.
+	pub warnings: Vec<(String, u16)>, // Warnings to show in the HUD and how many more ticks to show them for.
+	save_retry_ticks: u16, // How many ticks until the player and world overview are saved again after failing to save.
+	has_save_failed: bool, // If the player or world overview has failed to save.
+	ticks_since_autosave: u64,
+	autosave_tasks: Vec<JoinHandle<Result<(), Error>>>, // Player and world overview files being autosaved.
 }
 
 impl World {
@@ -25,9 +48,9 @@ impl World {
 	pub fn new(seed: u32, name: String, io: &IO, difficulty: Difficulty) -> Result<Self, Error> {
 		// Convert the world name to a world folder filepath, converting character that are not filename safe to underscores. Then create the folder.
 		let dirname: String = validate_filename(name.clone());
-		let mut filepath = io.worlds_path.clone();
+		let mut filepath = io.get_new_worlds_path().to_path_buf();
 		filepath.push(dirname);
-		create_dir(&filepath).map_err(|_| Error::CannotCreateFolder)?;
+		io.storage.create_folder(&filepath)?;
 		// Create overview path
 		let mut overview_filepath = filepath.clone();
 		overview_filepath.push("overview.wld".to_string());
@@ -36,33 +59,51 @@ impl World {
 			player: None,
 			chunk_pool: ChunkPool::new(),
 			seed,
+			generator_version: GENERATOR_VERSION,
+			world_id: rand::random(),
 			is_freeing: false,
 			is_freed: false,
 			name,
+			storage: io.storage.clone(),
 			filepath: filepath.clone(),
 			chunks_filepath: filepath.clone(),
+			region_files: Arc::new(RegionFiles::new(io.storage.clone(), filepath.clone())),
 			overview_filepath,
 			namespaces_filepath: filepath.clone(),
 			player_filepath: filepath.clone(),
 			difficulty,
+			time: 0,
+			warnings: Vec::new(),
+			save_retry_ticks: 0,
+			has_save_failed: false,
+			ticks_since_autosave: 0,
+			autosave_tasks: Vec::new(),
 		};
-		dummy_world.save_overview(io.namespace_hash);
+		dummy_world.save_overview(io.namespace_hash)?;
 		Self::load(filepath, io, false)
 	}
 
 	/// Load a world given the path to it's world folder.
 	pub fn load(filepath: PathBuf, io: &IO, basic: bool) -> Result<Self, Error> {
+		let storage = io.storage.clone();
+		// Backup the world before playing it
+		let mut warnings = Vec::new();
+		if !basic {
+			if let Err(error) = create_backup(&*storage, &io.backups_path, &filepath) {
+				warnings.push((format!("Unable to backup world: {error}"), WARNING_SHOW_TICKS));
+			}
+		}
 		// Get the path of the overview file for the world
 		let mut overview_filepath = filepath.clone();
 		overview_filepath.push("overview.wld".to_string());
 		// Get and create paths of the chunks and namespaces folders for the world
 		let mut chunks_filepath = filepath.clone();
 		chunks_filepath.push("chunks".to_string());
-		create_dir(&chunks_filepath).ok();
+		storage.create_folder(&chunks_filepath).ok();
 		let mut namespaces_filepath = filepath.clone();
 		namespaces_filepath.push("namespaces".to_string());
-		create_dir(&namespaces_filepath).ok();
-		if !chunks_filepath.exists() || !namespaces_filepath.exists() {
+		storage.create_folder(&namespaces_filepath).ok();
+		if !storage.exists(&chunks_filepath) || !storage.exists(&namespaces_filepath) {
 			return Err(Error::CannotCreateFolder);
 		}
 		// Get player filepath
@@ -71,42 +112,37 @@ impl World {
 		// Save namespace
 		let mut namespace_filepath = namespaces_filepath.clone();
 		namespace_filepath.push(format!("{:0>16x}.nsp", io.namespace_hash));
-		if !namespace_filepath.exists() {
-			io.namespace.write(&namespace_filepath).ok_or(Error::CannotSaveNamespace)?;
+		if !storage.exists(&namespace_filepath) {
+			io.namespace.write(&*storage, &namespace_filepath).map_err(|_| Error::CannotSaveNamespace)?;
 		}
 		// Read overview
-		let (mut overview_file, is_version_0) = FileReader::read_from_file(&overview_filepath)?;
-		let (_version, namespace) = if is_version_0 {
-			(0, None)
+		let WorldOverview { name, seed, difficulty, time, generator_version, world_id, .. } = WorldOverview::load(&*storage, &overview_filepath, &namespaces_filepath)?;
+		// Move chunks saved in the one file per chunk format into region files
+		let region_files = Arc::new(RegionFiles::new(storage.clone(), chunks_filepath.clone()));
+		if !basic {
+			region_files.migrate_chunk_files()?;
+		}
+		// Warn about tiles, items and entities that are not known, they are kept as placeholders
+		if !basic {
+			let unknown_names = get_unknown_names(&*storage, &namespaces_filepath);
+			if !unknown_names.is_empty() {
+				warnings.push((format!("Unknown names in save files, kept as placeholders: {}", unknown_names.join(", ")), WARNING_SHOW_TICKS));
+			}
 		}
-		else {
-			let namespace_hash = overview_file.read_u64()?;
-			let namespace = Namespace::load(namespace_hash, namespaces_filepath.clone())?;
-			(namespace.version, Some(namespace))
-		};
-		// Get world name
-		let name = match is_version_0 {
-			true => {
-				let index = overview_file.read_u32()?;
-				overview_file.get_string_v0(index).ok_or(Error::FileStringReadError)?
-			},
-			false => overview_file.read_string()?,
-		};
-		// Get world seed
-		let seed = overview_file.read_u32()?;
-		// Get difficulty
-		let difficulty = match is_version_0 {
-			true => Difficulty::Sandbox,
-			false => {
-				let difficulty_id = overview_file.read_u8()? as usize;
-				*namespace.expect("Constructed from Some() if is_version_0 is false.").difficulties.get(difficulty_id).ok_or(Error::IDOutOfNamespaceBounds)?
-			},
-		};
 		// Get player
 		let player = if !basic {
-			let player = Entity::load_player(&player_filepath, &namespaces_filepath, difficulty);
+			let player = Entity::load_player(&*storage, &player_filepath, &namespaces_filepath);
 			Some(match player {
 				Ok(player) => player,
+				// Keep a copy of a player file that could not be loaded as it will be overwritten by the new player
+				Err(error) if storage.exists(&player_filepath) => {
+					let unloadable_player_filepath = filepath.join("player_unloadable.ent");
+					match storage.read(&player_filepath).and_then(|data| storage.write(&unloadable_player_filepath, &data)) {
+						Ok(()) => warnings.push((format!("Unable to load player: {error} A new player was created and the old player file was kept as player_unloadable.ent."), WARNING_SHOW_TICKS)),
+						Err(_) => warnings.push((format!("Unable to load player: {error} A new player was created."), WARNING_SHOW_TICKS)),
+					}
+					Entity::new_player(difficulty)
+				}
 				Err(_) => Entity::new_player(difficulty),
 			})
 		}
@@ -118,20 +154,130 @@ impl World {
 			player,
 			chunk_pool: ChunkPool::new(),
 			seed,
+			generator_version,
+			// The overview is saved once the world is loaded so worlds saved without an ID keep the one they are given
+			world_id: world_id.unwrap_or_else(rand::random),
 			is_freeing: false,
 			is_freed: false,
 			name,
+			storage,
 			filepath,
 			chunks_filepath,
+			region_files,
 			overview_filepath,
 			namespaces_filepath,
 			player_filepath,
 			difficulty,
+			time,
+			warnings,
+			save_retry_ticks: 0,
+			has_save_failed: false,
+			ticks_since_autosave: 0,
+			autosave_tasks: Vec::new(),
 		};
-		world.save_overview(io.namespace_hash);
+		world.save_overview(io.namespace_hash)?;
 		Ok(world)
 	}
 
+	/// Get a world name and a path of a folder in a worlds folder that does not exist yet to save the world in.
+	/// If the folder for the name is taken then a number is added to the end of the name.
+	pub fn get_unused_name_and_filepath(name: &str, worlds_path: &Path, io: &IO) -> (String, PathBuf) {
+		let mut out = (name.to_string(), worlds_path.join(validate_filename(name.to_string())));
+		let mut number = 2;
+		while io.storage.exists(&out.1) {
+			let numbered_name = format!("{name} {number}");
+			out = (numbered_name.clone(), worlds_path.join(validate_filename(numbered_name)));
+			number += 1;
+		}
+		out
+	}
+
+	/// Change the name of a world that is not loaded and move it to a folder matching the new name in the same worlds folder if that folder is not taken, getting the new path of the world.
+	pub fn rename(filepath: PathBuf, new_name: String, io: &IO) -> Result<PathBuf, Error> {
+		// Write the new name to the overview
+		let mut world = Self::load(filepath.clone(), io, true)?;
+		world.name = new_name.clone();
+		world.save_overview(io.namespace_hash)?;
+		// Move the world folder
+		let new_filepath = filepath.with_file_name(validate_filename(new_name));
+		if new_filepath == filepath || io.storage.exists(&new_filepath) {
+			return Ok(filepath);
+		}
+		io.storage.rename(&filepath, &new_filepath)?;
+		Ok(new_filepath)
+	}
+
+	/// Copy a world that is not loaded into a new folder in the same worlds folder with " Copy" added to it's name.
+	pub fn duplicate(filepath: &Path, io: &IO) -> Result<(), Error> {
+		let world = Self::load(filepath.to_path_buf(), io, true)?;
+		let worlds_path = filepath.parent().unwrap_or(io.get_new_worlds_path());
+		let (new_name, new_filepath) = Self::get_unused_name_and_filepath(&format!("{} Copy", world.name), worlds_path, io);
+		if let Err(error) = copy_folder(&*io.storage, filepath, &new_filepath) {
+			io.storage.remove_folder(&new_filepath).ok();
+			return Err(error);
+		}
+		Self::set_new_world_id(&new_filepath, io)?;
+		Self::rename(new_filepath, new_name, io)?;
+		Ok(())
+	}
+
+	/// Give a world that is not loaded a new ID so that a copy of a world does not share backups with the original.
+	fn set_new_world_id(filepath: &Path, io: &IO) -> Result<(), Error> {
+		let mut world = Self::load(filepath.to_path_buf(), io, true)?;
+		world.world_id = rand::random();
+		world.save_overview(io.namespace_hash)
+	}
+
+	/// Pack a world that is not loaded into a single archive file that can be imported on another computer, erroring if there is already a file at the archive path.
+	pub fn export(filepath: &Path, archive_filepath: &Path, io: &IO) -> Result<(), Error> {
+		if io.storage.exists(archive_filepath) {
+			return Err(Error::FileAlreadyExists);
+		}
+		pack_world_folder(&*io.storage, filepath)?.write(&*io.storage, archive_filepath)
+	}
+
+	/// Import a world from an archive file into the worlds folder that new worlds are created in, getting the name of the world.
+	/// If there is already a world in a folder with the same name then a number is added to the end of the name of the imported world.
+	pub fn import(archive_filepath: &Path, io: &IO) -> Result<String, Error> {
+		let (mut file, _is_version_0) = FileReader::read_from_file(&*io.storage, archive_filepath)?;
+		// Unpack into a temporary folder in the data folder so a world that did not finish importing is not shown in the worlds folder, removing what is left from an import that did not finish
+		let importing_filepath = io.data_path.join("importing");
+		if io.storage.exists(&importing_filepath) {
+			io.storage.remove_folder(&importing_filepath)?;
+		}
+		if let Err(error) = unpack_world_archive(&*io.storage, &mut file, &importing_filepath) {
+			io.storage.remove_folder(&importing_filepath).ok();
+			return Err(error);
+		}
+		// Get the world name
+		let name = match Self::load(importing_filepath.clone(), io, true) {
+			Ok(world) => world.name,
+			Err(error) => {
+				io.storage.remove_folder(&importing_filepath).ok();
+				return Err(error);
+			}
+		};
+		// Move into a folder for the world, renaming the world if the folder for it's name is taken
+		// The folder is copied if it cannot be moved, for example because the worlds folder is on another drive
+		let (name, filepath) = Self::get_unused_name_and_filepath(&name, io.get_new_worlds_path(), io);
+		if io.storage.rename(&importing_filepath, &filepath).is_err() {
+			if let Err(error) = copy_folder(&*io.storage, &importing_filepath, &filepath) {
+				io.storage.remove_folder(&filepath).ok();
+				io.storage.remove_folder(&importing_filepath).ok();
+				return Err(error);
+			}
+			io.storage.remove_folder(&importing_filepath).ok();
+		}
+		Self::set_new_world_id(&filepath, io)?;
+		Self::rename(filepath, name.clone(), io)?;
+		Ok(name)
+	}
+
+	/// Delete a world that is not loaded.
+	pub fn delete(filepath: &Path, io: &IO) -> Result<(), Error> {
+		io.storage.remove_folder(filepath)
+	}
+
 	/// Render the world getting a vector of tris and the center pos of the camera.
 	/// The player will be in the center of the screen.
 	pub fn render(&mut self, player_visable_width: u64) -> (Vec<Vertex>, [f32; 2]) {
@@ -147,34 +293,233 @@ impl World {
 	/// Tick called when the game is not paused.
 	pub fn tick(&mut self, io: &IO, player_visable_width: u64, gui: &mut GUI) {
 		self.chunk_pool.tick(self.player.as_ref(), player_visable_width, &io.async_runtime, self.seed);
+		let is_night = self.is_night();
 		if let Some(player) = &mut self.player {
-			player.player_tick(&mut self.chunk_pool, io, gui, self.difficulty);
+			player.player_tick(&mut self.chunk_pool, io, gui, self.difficulty, self.seed, is_night);
 			player.tick(&mut self.chunk_pool);
 		}
+		self.time += 1;
+		// Autosave
+		self.ticks_since_autosave += 1;
+		let autosave_interval = io.settings.autosave_interval as u64 * TICKS_PER_SECOND;
+		if autosave_interval != 0 && self.ticks_since_autosave >= autosave_interval {
+			self.save(io);
+		}
+	}
+
+	/// Start saving the modified chunks, the player and the world overview in the background without freeing chunks.
+	pub fn save(&mut self, io: &IO) {
+		self.ticks_since_autosave = 0;
+		self.chunk_pool.autosave(&io.async_runtime, &self.region_files, io.namespace_hash);
+		// Do not write the player and overview files if they are still being written from the last save.
+		if !self.autosave_tasks.is_empty() {
+			return;
+		}
+		if let Some(player) = &self.player {
+			let file = player.serialize_player(io.namespace_hash);
+			let storage = self.storage.clone();
+			let player_filepath = self.player_filepath.clone();
+			self.autosave_tasks.push(io.async_runtime.spawn(async move { file.write(&*storage, &player_filepath) }));
+		}
+		let file = self.serialize_overview(io.namespace_hash);
+		let storage = self.storage.clone();
+		let overview_filepath = self.overview_filepath.clone();
+		self.autosave_tasks.push(io.async_runtime.spawn(async move { file.write(&*storage, &overview_filepath) }));
+	}
+
+	/// Weather it is currently night in the world, the second half of each day is night.
+	pub fn is_night(&self) -> bool {
+		self.time % TICKS_PER_DAY >= TICKS_PER_DAY / 2
 	}
 
 	/// Tick always called.
 	pub fn tick_always(&mut self, io: &IO, player_visable_width: u64, _gui: &mut GUI) {
-		self.chunk_pool.tick_always(self.player.as_ref(), player_visable_width, &io.async_runtime, self.seed, self.is_freeing, &mut self.is_freed, &self.chunks_filepath, &self.namespaces_filepath, io.namespace_hash);
-		if self.is_freeing {
-			if let Some(player) = &self.player {
-				player.save_player(&self.player_filepath, io.namespace_hash).unwrap();
+		let mut new_warnings = Vec::new();
+		let mut are_chunks_freed = false;
+		let chunk_pool_context = ChunkPoolContext {
+			seed: self.seed,
+			generator_version: self.generator_version,
+			region_files: &self.region_files,
+			namespaces_filepath: &self.namespaces_filepath,
+			namespace_hash: io.namespace_hash,
+			warnings: &mut new_warnings,
+		};
+		self.chunk_pool.tick_always(self.player.as_ref(), player_visable_width, &io.async_runtime, self.is_freeing, &mut are_chunks_freed, chunk_pool_context);
+		// Finish autosaving the player and overview
+		let waker = noop_waker();
+		let mut cx = Context::from_waker(&waker);
+		let mut autosave_tasks_left = Vec::new();
+		for mut autosave_task in self.autosave_tasks.drain(..) {
+			match autosave_task.poll_unpin(&mut cx) {
+				Poll::Pending => autosave_tasks_left.push(autosave_task),
+				Poll::Ready(Ok(Ok(()))) => {}
+				Poll::Ready(Ok(Err(error))) => {
+					new_warnings.push(format!("Unable to autosave world: {error}"));
+					self.has_save_failed = true;
+				}
+				Poll::Ready(Err(error)) => {
+					new_warnings.push(format!("Unable to autosave world: {error}"));
+					self.has_save_failed = true;
+				}
+			}
+		}
+		self.autosave_tasks = autosave_tasks_left;
+		// Save the player and overview once all chunks are saved and autosaves are done, trying again later if saving fails.
+		if are_chunks_freed && self.autosave_tasks.is_empty() && !self.is_freed {
+			match self.save_retry_ticks {
+				0 => match self.save_player_and_overview(io.namespace_hash) {
+					Ok(()) => self.is_freed = true,
+					Err(error) => {
+						new_warnings.push(format!("Unable to save world: {error}"));
+						self.has_save_failed = true;
+						self.save_retry_ticks = SAVE_RETRY_TICKS;
+					}
+				}
+				_ => self.save_retry_ticks -= 1,
 			}
 		}
+		// Count down how long to show warnings for and add new warnings, showing a warning that is already shown for longer instead of adding it again.
+		for (_, ticks_left) in self.warnings.iter_mut() {
+			*ticks_left = ticks_left.saturating_sub(1);
+		}
+		self.warnings.retain(|(_, ticks_left)| *ticks_left > 0);
+		for new_warning in new_warnings {
+			match self.warnings.iter_mut().find(|(warning, _)| *warning == new_warning) {
+				Some((_, ticks_left)) => *ticks_left = WARNING_SHOW_TICKS,
+				None => self.warnings.push((new_warning, WARNING_SHOW_TICKS)),
+			}
+		}
+	}
+
+	/// Save the player if there is one and the world overview.
+	fn save_player_and_overview(&self, namespace_hash: u64) -> Result<(), Error> {
+		if let Some(player) = &self.player {
+			player.save_player(&*self.storage, &self.player_filepath, namespace_hash)?;
+		}
+		self.save_overview(namespace_hash)
+	}
+
+	/// Weather saving any part of the world has failed, the world can then be closed without saving.
+	pub fn has_save_failed(&self) -> bool {
+		self.has_save_failed || self.chunk_pool.has_save_failed
+	}
+
+	pub fn save_overview(&self, namespace_hash: u64) -> Result<(), Error> {
+		self.serialize_overview(namespace_hash).write(&*self.storage, &self.overview_filepath)
 	}
 
-	pub fn save_overview(&self, namespace_hash: u64) {
-		// Create file
-		let mut file = FileWriter::new();
-		// Push namespace hash
-		file.push_u64(namespace_hash);
-		// Push world name
-		file.push_string(&self.name);
-		// Push seed
-		file.push_u32(self.seed);
-		// Push difficulty
-		file.push_u8(self.difficulty as u8);
-		// Write file
-		file.write(&self.overview_filepath);
+	/// Get the contents of the world overview file.
+	pub fn serialize_overview(&self, namespace_hash: u64) -> FileWriter {
+		let overview = WorldOverview {
+			name: self.name.clone(),
+			seed: self.seed,
+			difficulty: self.difficulty,
+			time: self.time,
+			generator_version: self.generator_version,
+			world_id: Some(self.world_id),
+			version: SERIALIZATION_VERSION,
+		};
+		overview.serialize(namespace_hash)
+	}
+}
+
+/// Get the names of the tiles, items and entities that are not known in each namespace file in a namespaces folder.
+fn get_unknown_names(storage: &dyn Storage, namespaces_filepath: &Path) -> Vec<String> {
+	let mut out = Vec::new();
+	let namespace_filepaths = match storage.list_folder(namespaces_filepath) {
+		Ok(namespace_filepaths) => namespace_filepaths,
+		Err(_) => return out,
+	};
+	for namespace_filepath in namespace_filepaths {
+		if let Ok(namespace) = Namespace::load_from_file(storage, &namespace_filepath) {
+			for name in namespace.get_unknown_names() {
+				if !out.contains(&name) {
+					out.push(name);
+				}
+			}
+		}
+	}
+	out.sort();
+	out
+}
+
+/// Copy a folder and everything in it to a folder that does not exist yet.
+fn copy_folder(storage: &dyn Storage, from: &Path, to: &Path) -> Result<(), Error> {
+	storage.create_folder(to)?;
+	for item_filepath in storage.list_folder(from)? {
+		let new_item_filepath = to.join(item_filepath.file_name().ok_or(Error::CannotReadFile)?);
+		match storage.is_folder(&item_filepath) {
+			true => copy_folder(storage, &item_filepath, &new_item_filepath)?,
+			false => storage.write(&new_item_filepath, &storage.read(&item_filepath)?)?,
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{path::PathBuf, sync::Arc, thread::sleep, time::Duration};
+
+	use crate::{gui::gui::GUI, io::{data_paths::DataPaths, io::IO, storage::MemoryStorage}, world::{chunk::chunk::Chunk, difficulty::Difficulty, direction::Direction4, entity::entity_type::EntityType, item::item::Item, tile::tile::Tile}};
+
+	use super::World;
+
+	/// Tick a world until a condition is met, failing if it takes too long.
+	fn tick_until(world: &mut World, io: &IO, mut condition: impl FnMut(&mut World) -> bool) {
+		let mut gui = GUI::new();
+		for _ in 0..1000 {
+			world.tick_always(io, 0, &mut gui);
+			if condition(world) {
+				return;
+			}
+			sleep(Duration::from_millis(10));
+		}
+		panic!("The world did not reach the expected state.");
+	}
+
+	#[test]
+	fn worlds_are_saved_and_loaded_in_memory() {
+		let io = IO::with_storage(Arc::new(MemoryStorage::new()), DataPaths::from_data_path(PathBuf::from("data")));
+		let mut world = World::new(42, "Test World".to_string(), &io, Difficulty::Hard).unwrap();
+		let filepath = world.filepath.clone();
+		// Save blank chunks around the player so they are loaded instead of generated, generating is slow without optimizations
+		for y in -1..=1 {
+			for x in -1..=1 {
+				world.region_files.write_chunk([x, y], &Chunk::new_blank().serialize(io.namespace_hash).unwrap()).unwrap();
+			}
+		}
+		// Change a chunk and the player
+		tick_until(&mut world, &io, |world| world.chunk_pool.get_tile_stack_at_mut([3, 4]).is_some());
+		let tile_stack = world.chunk_pool.get_tile_stack_at_mut([3, 4]).unwrap();
+		tile_stack.tiles = vec![Tile::Grass, Tile::Door(true)];
+		tile_stack.mark_modified();
+		let player = world.player.as_mut().unwrap();
+		player.pos = [5, 6];
+		player.facing = Direction4::West;
+		player.health = 37;
+		let EntityType::Player { inventory, .. } = &mut player.entity_type;
+		inventory.add_items((Item::Axe, 1));
+		world.time = 1234;
+		// Save and close the world
+		world.is_freeing = true;
+		tick_until(&mut world, &io, |world| world.is_freed);
+		assert!(!world.has_save_failed());
+		drop(world);
+
+		let mut world = World::load(filepath, &io, false).unwrap();
+		assert_eq!(world.name, "Test World");
+		assert_eq!(world.seed, 42);
+		assert!(world.difficulty == Difficulty::Hard);
+		assert_eq!(world.time, 1234);
+		let player = world.player.as_ref().unwrap();
+		assert_eq!(player.pos, [5, 6]);
+		assert_eq!(player.facing, Direction4::West);
+		assert_eq!(player.health, 37);
+		let EntityType::Player { inventory, .. } = &player.entity_type;
+		assert!(inventory.items[0] == (Item::Axe, 1));
+		tick_until(&mut world, &io, |world| world.chunk_pool.get_tile_stack_at_mut([3, 4]).is_some());
+		assert!(world.chunk_pool.get_tile_stack_at_mut([3, 4]).unwrap().tiles == [Tile::Grass, Tile::Door(true)]);
+		assert!(world.chunk_pool.get_tile_stack_at_mut([4, 4]).unwrap().tiles.is_empty());
+		assert!(world.warnings.is_empty());
 	}
 }
\ No newline at end of file
@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use crate::{io::{file_reader::FileReader, file_writer::FileWriter, namespace::Namespace, record::Record, storage::Storage}, error::Error};
+
+use super::difficulty::Difficulty;
+
+/// The contents of the overview file of a world.
+pub struct WorldOverview {
+	pub name: String,
+	pub seed: u32,
+	pub difficulty: Difficulty,
+	pub time: u64, // How many ticks the world has been played for.
+	pub generator_version: u32, // The version of the world generator that ungenerated chunks are generated with.
+	pub world_id: Option<u64>, // Stays the same when the world is renamed or moved so backups can be kept by it, worlds are given one when they are next loaded if they were saved without one.
+	pub version: u32, // The serialization version the overview was saved with.
+}
+
+impl WorldOverview {
+	/// Load a world overview file.
+	pub fn load(storage: &dyn Storage, overview_filepath: &Path, namespaces_filepath: &Path) -> Result<Self, Error> {
+		let (mut overview_file, is_version_0) = FileReader::read_from_file(storage, overview_filepath)?;
+		let (version, namespace) = if is_version_0 {
+			(0, None)
+		}
+		else {
+			let namespace_hash = overview_file.read_u64()?;
+			let namespace = Namespace::load(storage, namespace_hash, namespaces_filepath)?;
+			(namespace.version, Some(namespace))
+		};
+		// Overviews saved from version 9 are a record of fields
+		if version >= 9 {
+			let namespace = namespace.expect("Constructed from Some() if is_version_0 is false.");
+			let record = Record::deserialize(&mut overview_file)?;
+			return Ok(Self {
+				name: record.read_required_value("name", &namespace, version)?,
+				seed: record.read_required_value("seed", &namespace, version)?,
+				difficulty: record.read_required_value("difficulty", &namespace, version)?,
+				time: record.read_value("time", &namespace, version)?.unwrap_or(0),
+				generator_version: record.read_value("generator_version", &namespace, version)?.unwrap_or(0),
+				world_id: record.read_value("world_id", &namespace, version)?,
+				version,
+			});
+		}
+		// Get world name
+		let name = match is_version_0 {
+			true => {
+				let index = overview_file.read_u32()?;
+				overview_file.get_string_v0(index).ok_or(Error::FileStringReadError)?
+			},
+			false => overview_file.read_string()?,
+		};
+		// Get world seed
+		let seed = overview_file.read_u32()?;
+		// Get difficulty
+		let difficulty = match is_version_0 {
+			true => Difficulty::Sandbox,
+			false => {
+				let difficulty_id = overview_file.read_u8()? as usize;
+				namespace.expect("Constructed from Some() if is_version_0 is false.").difficulties.get(difficulty_id)?
+			},
+		};
+		// Get world time
+		let time = match version {
+			0..=2 => 0,
+			_ => overview_file.read_u64()?,
+		};
+		Ok(Self {
+			name,
+			seed,
+			difficulty,
+			time,
+			generator_version: 0,
+			world_id: None,
+			version,
+		})
+	}
+
+	/// Get the contents of the world overview file.
+	pub fn serialize(&self, namespace_hash: u64) -> FileWriter {
+		// Create file
+		let mut file = FileWriter::new();
+		// Push namespace hash
+		file.push_u64(namespace_hash);
+		// Push fields
+		file.push_value_field("name", &self.name);
+		file.push_value_field("seed", &self.seed);
+		file.push_value_field("difficulty", &self.difficulty);
+		file.push_value_field("time", &self.time);
+		file.push_value_field("generator_version", &self.generator_version);
+		if let Some(world_id) = &self.world_id {
+			file.push_value_field("world_id", world_id);
+		}
+		file.push_record_end();
+		file
+	}
+}
\ No newline at end of file
@@ -0,0 +1,196 @@
+use std::{collections::{BTreeMap, BTreeSet}, path::Path};
+
+use crate::{error::Error, io::{io::IO, file_reader::FileReader, file_writer::CHECKSUM_MAGIC, namespace::Namespace, region_files::RegionFiles, storage::Storage, backups::create_backup}};
+
+use super::{world::World, world_overview::WorldOverview, chunk::chunk::Chunk, entity::entity::Entity};
+
+/// What was done when upgrading a world.
+pub struct UpgradeReport {
+	pub overview_version: u32, // The version the overview was saved with before upgrading.
+	pub player_version: Option<u32>, // The version the player was saved with before upgrading if there is a player file.
+	pub chunk_files_migrated: usize, // How many chunks were moved from one chunk per file into region files.
+	pub chunks_upgraded: BTreeMap<u32, usize>, // How many chunks were upgraded from each version.
+	pub chunks_up_to_date: usize,
+	pub failed_chunks: Vec<([i64; 2], Error)>, // Chunks that could not be upgraded and why.
+	pub namespaces_deleted: usize, // How many namespace files that were no longer used were deleted.
+	pub namespaces_kept: Vec<u64>, // Hashes of old namespaces that were kept as quarantined chunks or an unloadable player file still use them.
+}
+
+/// Get the serialization version a file was saved with from it's namespace.
+fn get_file_version(storage: &dyn Storage, file: &mut FileReader, is_version_0: bool, namespaces_filepath: &Path) -> Result<(u32, u64), Error> { // Version and namespace hash.
+	if is_version_0 {
+		return Ok((0, 0));
+	}
+	let namespace_hash = file.read_u64()?;
+	Ok((Namespace::load(storage, namespace_hash, namespaces_filepath)?.version, namespace_hash))
+}
+
+/// Get the namespace hash at the start of the data of a saved file without verifying the checksum, as files kept for recovery can be damaged.
+fn read_namespace_hash_unchecked(data: &[u8]) -> Option<u64> {
+	let hash_start = match data.get(0..4)? {
+		magic if magic == CHECKSUM_MAGIC => 12,
+		// Version 0 files have a pointer to their strings before the body
+		[0, 0, 0, 0] => 8,
+		_ => 0,
+	};
+	Some(u64::from_le_bytes(data.get(hash_start..hash_start + 8)?.try_into().expect("[u8] of length 8 should be castable to [u8; 8].")))
+}
+
+/// Get the hashes of the namespaces used by quarantined chunks and the unloadable player file of a world, these files are not upgraded so their namespaces are still needed.
+/// None is returned if a file does not refer to a namespace that exists, in which case which namespaces are still needed is not known.
+fn get_recovery_namespace_hashes(storage: &dyn Storage, filepath: &Path, chunks_filepath: &Path, namespace_hashes: &BTreeSet<u64>) -> Result<Option<BTreeSet<u64>>, Error> {
+	let mut recovery_filepaths = Vec::new();
+	let quarantine_filepath = chunks_filepath.join("quarantine");
+	if storage.exists(&quarantine_filepath) {
+		recovery_filepaths.extend(storage.list_folder(&quarantine_filepath)?.into_iter().filter(|quarantined_filepath| quarantined_filepath.extension().and_then(|extension| extension.to_str()) == Some("cnk")));
+	}
+	let unloadable_player_filepath = filepath.join("player_unloadable.ent");
+	if storage.exists(&unloadable_player_filepath) {
+		recovery_filepaths.push(unloadable_player_filepath);
+	}
+	let mut recovery_namespace_hashes = BTreeSet::new();
+	for recovery_filepath in recovery_filepaths {
+		match read_namespace_hash_unchecked(&storage.read(&recovery_filepath)?) {
+			Some(namespace_hash) if namespace_hashes.contains(&namespace_hash) => recovery_namespace_hashes.insert(namespace_hash),
+			_ => return Ok(None),
+		};
+	}
+	Ok(Some(recovery_namespace_hashes))
+}
+
+/// Load every file in a world that is not loaded and save it again with the current serialization version, then delete namespace files that are no longer used.
+/// The world is backed up before it is upgraded.
+pub fn upgrade_world(filepath: &Path, io: &IO) -> Result<UpgradeReport, Error> {
+	let storage = &*io.storage;
+	create_backup(storage, &io.backups_path, filepath)?;
+	let namespaces_filepath = filepath.join("namespaces");
+	// Get the overview version then load the world, which saves the overview with the current version
+	let overview_version = WorldOverview::load(storage, &filepath.join("overview.wld"), &namespaces_filepath)?.version;
+	let world = World::load(filepath.to_path_buf(), io, true)?;
+	// Upgrade the player
+	let player_version = match storage.exists(&world.player_filepath) {
+		true => {
+			let (mut player_file, is_version_0) = FileReader::read_from_file(storage, &world.player_filepath)?;
+			let (player_version, _) = get_file_version(storage, &mut player_file, is_version_0, &namespaces_filepath)?;
+			let player = Entity::load_player(storage, &world.player_filepath, &namespaces_filepath)?;
+			player.save_player(storage, &world.player_filepath, io.namespace_hash)?;
+			Some(player_version)
+		}
+		false => None,
+	};
+	// Upgrade chunks
+	let region_files = RegionFiles::new(io.storage.clone(), world.chunks_filepath.clone());
+	let chunk_files_migrated = region_files.migrate_chunk_files()?;
+	let mut chunks_upgraded = BTreeMap::new();
+	let mut chunks_up_to_date = 0;
+	let mut failed_chunks = Vec::new();
+	for chunk_pos in region_files.list_chunks()? {
+		match upgrade_chunk(chunk_pos, &region_files, &namespaces_filepath, io) {
+			Ok(None) => chunks_up_to_date += 1,
+			Ok(Some(version)) => *chunks_upgraded.entry(version).or_insert(0) += 1,
+			Err(error) => failed_chunks.push((chunk_pos, error)),
+		}
+	}
+	// Delete unused namespaces if everything now uses the current namespace, namespaces used by files kept for recovery are kept
+	let mut namespaces_deleted = 0;
+	let mut namespaces_kept = Vec::new();
+	if failed_chunks.is_empty() {
+		let mut namespace_filepaths = BTreeMap::new();
+		for namespace_filepath in storage.list_folder(&namespaces_filepath)? {
+			let namespace_hash = namespace_filepath.file_name().and_then(|filename| filename.to_str()).and_then(|filename| filename.strip_suffix(".nsp")).and_then(|hash| u64::from_str_radix(hash, 16).ok());
+			match namespace_hash {
+				Some(namespace_hash) if namespace_hash != io.namespace_hash => { namespace_filepaths.insert(namespace_hash, namespace_filepath); }
+				_ => {}
+			}
+		}
+		let namespace_hashes = namespace_filepaths.keys().copied().collect();
+		// Keep every namespace if it is not known which namespaces files kept for recovery use
+		let recovery_namespace_hashes = get_recovery_namespace_hashes(storage, filepath, &world.chunks_filepath, &namespace_hashes)?.unwrap_or(namespace_hashes);
+		for (namespace_hash, namespace_filepath) in namespace_filepaths {
+			match recovery_namespace_hashes.contains(&namespace_hash) {
+				true => namespaces_kept.push(namespace_hash),
+				false => {
+					storage.remove_file(&namespace_filepath)?;
+					namespaces_deleted += 1;
+				}
+			}
+		}
+	}
+	Ok(UpgradeReport {
+		overview_version,
+		player_version,
+		chunk_files_migrated,
+		chunks_upgraded,
+		chunks_up_to_date,
+		failed_chunks,
+		namespaces_deleted,
+		namespaces_kept,
+	})
+}
+
+/// Save a chunk again with the current serialization version if it was saved with a different namespace, getting the version it was saved with if it was upgraded.
+fn upgrade_chunk(chunk_pos: [i64; 2], region_files: &RegionFiles, namespaces_filepath: &Path, io: &IO) -> Result<Option<u32>, Error> {
+	let data = region_files.read_chunk(chunk_pos)?.ok_or(Error::CannotReadFile)?;
+	let (mut file, is_version_0) = FileReader::read_from_data(data)?;
+	let (version, namespace_hash) = get_file_version(&*region_files.storage, &mut file, is_version_0, namespaces_filepath)?;
+	if !is_version_0 && namespace_hash == io.namespace_hash {
+		return Ok(None);
+	}
+	let mut chunk = Chunk::new_blank();
+	chunk.load(chunk_pos, region_files, namespaces_filepath.to_path_buf())?;
+	region_files.write_chunk(chunk_pos, &chunk.serialize(io.namespace_hash).ok_or(Error::CannotWriteFile)?)?;
+	Ok(Some(version))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{path::{Path, PathBuf}, sync::Arc};
+
+	use crate::{io::{data_paths::DataPaths, io::{IO, SERIALIZATION_VERSION}, namespace::Namespace, storage::MemoryStorage}, world::{chunk::chunk::Chunk, difficulty::Difficulty, entity::entity::Entity, world::World}};
+
+	use super::upgrade_world;
+
+	/// Create a world with namespace files saved under the hashes 1 and 2 as if the world was played with older versions.
+	fn create_world_with_old_namespaces(io: &IO) -> PathBuf {
+		let filepath = World::new(42, "Test World".to_string(), io, Difficulty::Hard).unwrap().filepath.clone();
+		let current = Namespace::current();
+		for namespace_hash in [1, 2] {
+			let categories = current.get_categories().into_iter().map(|(category, names)| (category.to_string(), names.clone())).collect();
+			let namespace = Namespace::from_categories(SERIALIZATION_VERSION - 1, categories).unwrap();
+			namespace.serialize().write(&*io.storage, &filepath.join("namespaces").join(format!("{namespace_hash:0>16x}.nsp"))).unwrap();
+		}
+		filepath
+	}
+
+	fn namespace_exists(io: &IO, filepath: &Path, namespace_hash: u64) -> bool {
+		io.storage.exists(&filepath.join("namespaces").join(format!("{namespace_hash:0>16x}.nsp")))
+	}
+
+	#[test]
+	fn namespaces_of_quarantined_chunks_are_kept() {
+		let io = IO::with_storage(Arc::new(MemoryStorage::new()), DataPaths::from_data_path(PathBuf::from("data")));
+		let filepath = create_world_with_old_namespaces(&io);
+		let quarantine_filepath = filepath.join("chunks").join("quarantine");
+		io.storage.create_folder(&quarantine_filepath).unwrap();
+		io.storage.write(&quarantine_filepath.join("0 0 0.cnk"), &Chunk::new_blank().serialize(1).unwrap()).unwrap();
+		let report = upgrade_world(&filepath, &io).unwrap();
+		assert_eq!(report.namespaces_kept, vec![1]);
+		assert_eq!(report.namespaces_deleted, 1);
+		assert!(namespace_exists(&io, &filepath, 1));
+		assert!(!namespace_exists(&io, &filepath, 2));
+		assert!(namespace_exists(&io, &filepath, io.namespace_hash));
+	}
+
+	#[test]
+	fn namespaces_are_kept_if_an_unloadable_player_uses_an_unknown_namespace() {
+		let io = IO::with_storage(Arc::new(MemoryStorage::new()), DataPaths::from_data_path(PathBuf::from("data")));
+		let filepath = create_world_with_old_namespaces(&io);
+		let data = Entity::new_player(Difficulty::Hard).serialize_player(3).write_to_vec().unwrap();
+		io.storage.write(&filepath.join("player_unloadable.ent"), &data).unwrap();
+		let report = upgrade_world(&filepath, &io).unwrap();
+		assert_eq!(report.namespaces_kept, vec![1, 2]);
+		assert_eq!(report.namespaces_deleted, 0);
+		assert!(namespace_exists(&io, &filepath, 1));
+		assert!(namespace_exists(&io, &filepath, 2));
+	}
+}
\ No newline at end of file